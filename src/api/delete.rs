@@ -6,6 +6,7 @@ use crate::client::delete;
 use crate::error::NeocitiesErr;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
+use url::form_urlencoded::byte_serialize;
 
 /// Handles the request to delete file(s) from a Neocities website using the
 /// following endpoint: `/api/delete`
@@ -25,7 +26,9 @@ pub struct DeleteResponse {
 }
 
 impl NcDelete {
-    fn request_info(args: Vec<String>) -> Result<HttpRequestInfo, NeocitiesErr> {
+    /// Prepares the information for a request to `/api/delete`, including the credentials found in
+    /// the environment, without sending it.
+    pub fn request_info(args: Vec<String>) -> Result<HttpRequestInfo, NeocitiesErr> {
         let url: String;
         let api_key: Option<String>;
         let cred = Credentials::new();
@@ -44,8 +47,10 @@ impl NcDelete {
             if files.len() > 0 {
                 files.push_str("&");
             }
+            // encode names so that characters like `&`, `+` and spaces survive the request body
+            let encoded: String = byte_serialize(arg.as_bytes()).collect();
             files.push_str("filenames[]=");
-            files.push_str(arg);
+            files.push_str(&encoded);
         }
         let pk = HttpRequestInfo {
             uri: url,
@@ -102,6 +107,22 @@ mod tests {
         });
     }
 
+    #[test]
+    #[serial(env)]
+    fn delete_request_body_encoding() {
+        Credentials::run_inside_temp_env(None, None, Some("foo"), &|| {
+            let pk = NcDelete::request_info(vec![
+                String::from("a&b.html"),
+                String::from("my file+1.html"),
+            ])
+            .unwrap();
+            assert_eq!(
+                pk.body.unwrap(),
+                "filenames[]=a%26b.html&filenames[]=my+file%2B1.html"
+            );
+        });
+    }
+
     #[test]
     fn convert_value_to_delete_response() {
         let mock_str_1 = r#"
@@ -1,7 +1,13 @@
 use crate::error::NeocitiesErr;
+use futures_util::StreamExt;
 use reqwest::{header::AUTHORIZATION, multipart, Body, Client, StatusCode};
-use std::path::PathBuf;
-use tokio::fs::File;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    fs::File,
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+    time::Instant,
+};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 /// Contains specific data for forming http requests to interact with the Neocities API.
@@ -16,6 +22,51 @@ pub struct HttpRequestInfo {
     pub multipart: Option<Vec<String>>,
 }
 
+/// Receives the remote path of a file and the number of its bytes sent so far
+type ProgressFn = dyn Fn(&str, u64) + Send + Sync;
+
+/// Watches the bytes of an upload as they stream to the server, reporting them and, if a rate is
+/// given, holding them back to stay under it. A single `Transfer` can be shared by every request of
+/// an upload, so that the rate applies to all of them together.
+pub struct Transfer {
+    /// Called with the remote path of a file and the number of its bytes sent so far
+    progress: Box<ProgressFn>,
+    /// The most bytes to send per second, if any
+    limit_rate: Option<u64>,
+    /// The time at which the next chunk may be sent under the rate
+    next_send: Mutex<Instant>,
+}
+
+impl Transfer {
+    /// A constructor that returns an instance of `Transfer`.
+    pub fn new(
+        limit_rate: Option<u64>,
+        progress: impl Fn(&str, u64) + Send + Sync + 'static,
+    ) -> Transfer {
+        Transfer {
+            progress: Box::new(progress),
+            limit_rate,
+            next_send: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until a chunk of `bytes` may be sent under the rate, then reports that `sent` bytes
+    /// of the file at `path` are on their way.
+    async fn send_chunk(&self, path: &str, bytes: u64, sent: u64) {
+        if let Some(rate) = self.limit_rate {
+            let start = {
+                let mut next = self.next_send.lock().await;
+                let start = (*next).max(Instant::now());
+                *next = start + Duration::from_secs_f64(bytes as f64 / rate.max(1) as f64);
+                start
+            };
+            tokio::time::sleep_until(start).await;
+        }
+
+        (self.progress)(path, sent);
+    }
+}
+
 /// Prepares and sends a GET request to the Neocities API. It awaits a response and returns either a
 /// response body or an error.
 #[tokio::main]
@@ -71,17 +122,102 @@ pub async fn post_request_multipart(
     api_key: Option<String>,
     multipart: Option<Vec<String>>,
 ) -> Result<serde_json::Value, NeocitiesErr> {
+    // each file is uploaded to the same path it has locally
+    let files = multipart.map(|a| a.into_iter().map(|p| (p.clone(), p)).collect());
+    send_multipart(&Client::new(), uri, api_key, files, None).await
+}
+
+/// Prepares and sends a POST request to the Neocities API containing multipart/form-data, where
+/// each local file is paired with the remote path it is uploaded to.
+#[tokio::main]
+pub async fn post_request_multipart_as(
+    uri: String,
+    api_key: Option<String>,
+    files: Vec<(String, String)>,
+) -> Result<serde_json::Value, NeocitiesErr> {
+    send_multipart(&Client::new(), uri, api_key, Some(files), None).await
+}
+
+/// Sends several POST requests containing multipart/form-data to the Neocities API over a shared
+/// client. At most `jobs` requests are in flight at once, and the start of any two requests is at
+/// least `interval` apart, however many are in flight. If a `Transfer` is given, the files are
+/// streamed through it. `on_done` is called with the index of each
+/// request as its response arrives, so it must not send requests of its own.
+#[tokio::main]
+pub async fn post_requests_multipart(
+    uri: String,
+    api_key: Option<String>,
+    requests: Vec<Vec<String>>,
+    jobs: usize,
+    interval: Duration,
+    transfer: Option<Arc<Transfer>>,
+    on_done: &mut dyn FnMut(usize, Result<serde_json::Value, NeocitiesErr>),
+) {
     let client = Client::new();
+    let permits = Arc::new(Semaphore::new(jobs.max(1)));
+    let next_start = Arc::new(Mutex::new(Instant::now()));
+
+    let mut tasks = JoinSet::new();
+    let mut indexes = std::collections::HashMap::new();
+
+    for (i, paths) in requests.into_iter().enumerate() {
+        let client = client.clone();
+        let permits = permits.clone();
+        let next_start = next_start.clone();
+        let uri = uri.clone();
+        let api_key = api_key.clone();
+        let transfer = transfer.clone();
+
+        let handle = tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+
+            // claim the next free slot under the rate limit before sending
+            let start = {
+                let mut next = next_start.lock().await;
+                let start = (*next).max(Instant::now());
+                *next = start + interval;
+                start
+            };
+            tokio::time::sleep_until(start).await;
+
+            // each file is uploaded to the same path it has locally
+            let files = paths.into_iter().map(|p| (p.clone(), p)).collect();
+            send_multipart(&client, uri, api_key, Some(files), transfer)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        indexes.insert(handle.id(), i);
+    }
+
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, res) = match joined {
+            Ok((id, res)) => (id, res),
+            Err(e) => (e.id(), Err(e.to_string())),
+        };
+
+        if let Some(i) = indexes.get(&id) {
+            on_done(
+                *i,
+                res.map_err(|e| NeocitiesErr::HttpRequestError(e.into())),
+            );
+        }
+    }
+}
+
+async fn send_multipart(
+    client: &Client,
+    uri: String,
+    api_key: Option<String>,
+    multipart: Option<Vec<(String, String)>>,
+    transfer: Option<Arc<Transfer>>,
+) -> Result<serde_json::Value, NeocitiesErr> {
     let mut form = multipart::Form::new();
 
     if let Some(a) = multipart {
-        for arg in a.iter() {
+        for (arg, remote) in a.iter() {
             let path = PathBuf::from(&arg);
 
-            let filepath: String;
-            if let Some(p) = path.to_str() {
-                filepath = p.to_string();
-            } else {
+            if path.to_str().is_none() {
                 return Err(NeocitiesErr::HttpRequestError(
                     format!("problem with file/path: {arg}").into(),
                 ));
@@ -89,10 +225,25 @@ pub async fn post_request_multipart(
 
             let file = File::open(path).await?;
             let stream = FramedRead::new(file, BytesCodec::new());
-            let file_body = Body::wrap_stream(stream);
+            let file_body = match transfer.clone() {
+                Some(t) => {
+                    let remote = remote.clone();
+                    let mut sent: u64 = 0;
+                    Body::wrap_stream(stream.then(move |chunk| {
+                        let bytes = chunk.as_ref().map_or(0, |c| c.len() as u64);
+                        sent += bytes;
+                        let (t, remote, sent) = (t.clone(), remote.clone(), sent);
+                        async move {
+                            t.send_chunk(&remote, bytes, sent).await;
+                            chunk
+                        }
+                    }))
+                }
+                None => Body::wrap_stream(stream),
+            };
 
-            let some_file = multipart::Part::stream(file_body).file_name(filepath.clone());
-            form = form.part(filepath, some_file);
+            let some_file = multipart::Part::stream(file_body).file_name(remote.clone());
+            form = form.part(remote.clone(), some_file);
         }
     } else {
         return Err(NeocitiesErr::HttpRequestError(
@@ -198,6 +349,61 @@ pub async fn post_request_body(
     ));
 }
 
+/// Sends a GET request for a file on a public website, such as a Neocities user's site, and
+/// returns the raw bytes of the response body.
+#[tokio::main]
+pub async fn get_file(uri: String) -> Result<Vec<u8>, NeocitiesErr> {
+    let res = match reqwest::get(uri.as_str()).await {
+        Ok(r) => r,
+        Err(e) => return Err(NeocitiesErr::HttpRequestError(e.into())),
+    };
+
+    if let reqwest::StatusCode::OK = res.status() {
+        return match res.bytes().await {
+            Ok(b) => Ok(b.to_vec()),
+            Err(e) => Err(NeocitiesErr::HttpRequestError(e.into())),
+        };
+    }
+
+    Err(NeocitiesErr::HttpRequestError(
+        status_message(res.status()).into(),
+    ))
+}
+
+/// Sends a GET request for a file on a public website and writes the response body to `writer`
+/// as it arrives, without holding the whole file in memory. Returns the number of bytes written.
+#[tokio::main]
+pub async fn stream_file(
+    uri: String,
+    writer: &mut dyn std::io::Write,
+) -> Result<u64, NeocitiesErr> {
+    let mut res = match reqwest::get(uri.as_str()).await {
+        Ok(r) => r,
+        Err(e) => return Err(NeocitiesErr::HttpRequestError(e.into())),
+    };
+
+    if res.status() != reqwest::StatusCode::OK {
+        return Err(NeocitiesErr::HttpRequestError(
+            status_message(res.status()).into(),
+        ));
+    }
+
+    let mut written: u64 = 0;
+    loop {
+        match res.chunk().await {
+            Ok(Some(chunk)) => {
+                writer.write_all(&chunk)?;
+                written += chunk.len() as u64;
+            }
+            Ok(None) => break,
+            Err(e) => return Err(NeocitiesErr::HttpRequestError(e.into())),
+        }
+    }
+    writer.flush()?;
+
+    Ok(written)
+}
+
 fn status_message(code: StatusCode) -> String {
     match code {
         StatusCode::BAD_REQUEST => {
@@ -228,7 +434,16 @@ fn status_message(code: StatusCode) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{get_request, post_request_body, post_request_multipart};
+    use super::{
+        get_request, post_request_body, post_request_multipart, post_requests_multipart,
+        stream_file, Transfer,
+    };
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
 
     #[test]
     fn basic_get_request() {
@@ -257,4 +472,132 @@ mod tests {
         );
         assert_eq!(res.is_ok(), true);
     }
+
+    #[test]
+    fn concurrent_post_requests_multipart() {
+        let started = Instant::now();
+        let mut done: Vec<usize> = Vec::new();
+
+        // nothing listens on the port, so every request fails without leaving the machine
+        post_requests_multipart(
+            "http://127.0.0.1:9/upload".to_string(),
+            None,
+            vec![vec!["./tests/fixtures/foo.html".to_string()]; 3],
+            2,
+            Duration::from_millis(50),
+            None,
+            &mut |i, res| {
+                assert_eq!(res.is_err(), true);
+                done.push(i);
+            },
+        );
+
+        done.sort();
+        assert_eq!(done, vec![0, 1, 2]);
+
+        // the third request waited for two intervals to pass
+        assert_eq!(started.elapsed() >= Duration::from_millis(100), true);
+    }
+
+    #[tokio::test]
+    async fn transfer_limit_rate() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let t = Transfer::new(Some(1000), move |path, n| {
+            log.lock().unwrap().push((path.to_string(), n))
+        });
+
+        let started = Instant::now();
+        t.send_chunk("a.html", 100, 100).await;
+        t.send_chunk("a.html", 100, 200).await;
+        t.send_chunk("a.html", 100, 300).await;
+
+        // at 1000 bytes per second, each chunk of 100 bytes waits for the one before it
+        assert_eq!(started.elapsed() >= Duration::from_millis(200), true);
+        assert_eq!(
+            sent.lock().unwrap().last(),
+            Some(&(String::from("a.html"), 300))
+        );
+    }
+
+    #[test]
+    fn transfer_progress() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let uri = format!("http://{}/upload", listener.local_addr().unwrap());
+
+        // a server that reads the whole chunked request before answering
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0; 4096];
+            while !received.ends_with(b"0\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                received.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"result":"success"}"#;
+            let res = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(res.as_bytes()).unwrap();
+        });
+
+        let sent = Arc::new(Mutex::new(0));
+        let log = sent.clone();
+        let t = Transfer::new(None, move |_, n| *log.lock().unwrap() = n);
+
+        let mut results = Vec::new();
+        post_requests_multipart(
+            uri,
+            None,
+            vec![vec!["./tests/fixtures/foo.html".to_string()]],
+            1,
+            Duration::ZERO,
+            Some(Arc::new(t)),
+            &mut |_, res| results.push(res.is_ok()),
+        );
+        server.join().unwrap();
+
+        assert_eq!(results, vec![true]);
+        assert_eq!(
+            *sent.lock().unwrap(),
+            std::fs::metadata("./tests/fixtures/foo.html")
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn stream_file_function() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // answers the first request with a file and the second with a 404
+        let server = std::thread::spawn(move || {
+            for res in [
+                "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world",
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).unwrap();
+                stream.write_all(res.as_bytes()).unwrap();
+            }
+        });
+
+        let mut output = Vec::new();
+        let written = stream_file(format!("http://{addr}/hello.txt"), &mut output).unwrap();
+        assert_eq!(written, 11);
+        assert_eq!(output, b"hello world");
+
+        let mut output = Vec::new();
+        let res = stream_file(format!("http://{addr}/missing.txt"), &mut output);
+        assert_eq!(res.is_err(), true);
+        assert_eq!(output.is_empty(), true);
+
+        server.join().unwrap();
+    }
 }
@@ -21,3 +21,6 @@ pub mod upload;
 
 /// Prepares and sends a request to delete files from a user's site
 pub mod delete;
+
+/// Prepares and sends a request to rename, or move, a file on a user's site
+pub mod rename;
@@ -0,0 +1,103 @@
+use super::credentials::{Auth, Credentials};
+use super::http::{post_request_body, HttpRequestInfo};
+use crate::error::NeocitiesErr;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use url::form_urlencoded::Serializer;
+
+/// The path of the Neocities API endpoint used to rename files
+pub const PATH: &str = "rename";
+
+/// Handles the request to rename, or move, a file on a Neocities website using the following
+/// endpoint: `/api/rename`
+pub struct NcRename {}
+
+/// Contains data received from Neocities in response to a request to `/api/rename`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameResponse {
+    /// A status message
+    pub result: String,
+    /// An error message
+    #[serde(rename = "error_type")]
+    pub error_type: Option<String>,
+    /// An explanation of the rename operation that has occurred
+    pub message: String,
+}
+
+impl NcRename {
+    fn request_info(path: &str, new_path: &str) -> Result<HttpRequestInfo, NeocitiesErr> {
+        let cred = Credentials::new();
+        let auth = Auth::authenticate(cred, PATH, None);
+
+        let (uri, api_key) = match auth {
+            Ok(a) => (a.url, a.api_key),
+            Err(e) => return Err(NeocitiesErr::HttpRequestError(e.into())),
+        };
+
+        let body = Serializer::new(String::new())
+            .append_pair("path", path)
+            .append_pair("new_path", new_path)
+            .finish();
+
+        Ok(HttpRequestInfo {
+            uri,
+            api_key,
+            body: Some(body),
+            multipart: None,
+        })
+    }
+
+    fn to_rename_response(value: serde_json::Value) -> Result<RenameResponse, NeocitiesErr> {
+        match serde_json::from_value(value) {
+            Ok(res) => Ok(res),
+            Err(e) => Err(NeocitiesErr::SerdeDeserializationError(e)),
+        }
+    }
+
+    /// Prepares and sends a request to rename the file at `path` to `new_path` on a Neocities
+    /// user's website. It awaits a response and returns either a RenameResponse or an error.
+    pub fn fetch(path: &str, new_path: &str) -> Result<RenameResponse, NeocitiesErr> {
+        let req_info = NcRename::request_info(path, new_path)?;
+        let res = post_request_body(req_info.uri, req_info.api_key, req_info.body)?;
+        let rr = NcRename::to_rename_response(res)?;
+        Ok(rr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NcRename, RenameResponse};
+    use crate::api::credentials::Credentials;
+    use serial_test::serial;
+
+    #[test]
+    #[serial(env)]
+    fn rename_request_info() {
+        Credentials::run_inside_temp_env(None, None, Some("foo"), &|| {
+            let ri = NcRename::request_info("img/a b.png", "images/a&b.png").unwrap();
+            assert_eq!(ri.api_key.unwrap(), "foo");
+            assert_eq!(ri.uri, "https://neocities.org/api/rename");
+            assert_eq!(
+                ri.body.unwrap(),
+                "path=img%2Fa+b.png&new_path=images%2Fa%26b.png"
+            );
+        });
+    }
+
+    #[test]
+    fn value_to_rename_response() {
+        let mock_str = r#"
+        {
+          "result": "success",
+          "message": "img/cat.png has been renamed to images/cat.png"
+        }"#;
+
+        let v: serde_json::Value = serde_json::from_str(mock_str).unwrap();
+        let rr: RenameResponse = NcRename::to_rename_response(v).unwrap();
+
+        assert_eq!(rr.result, "success");
+        assert_eq!(rr.error_type, None);
+        assert_eq!(rr.message, "img/cat.png has been renamed to images/cat.png");
+    }
+}
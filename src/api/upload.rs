@@ -1,9 +1,17 @@
 use super::credentials::{Auth, Credentials};
-use super::http::{post_request_multipart, HttpRequestInfo};
+use super::http::{
+    post_request_multipart, post_request_multipart_as, post_requests_multipart, HttpRequestInfo,
+    Transfer,
+};
 use crate::client::upload;
 use crate::error::NeocitiesErr;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
+use std::{sync::Arc, time::Duration};
+
+/// The shortest time between the start of two upload requests, however many are sent at once,
+/// which keeps concurrent uploads within the rate limit of the Neocities API
+pub const REQUEST_INTERVAL: Duration = Duration::from_millis(250);
 
 /// Handles the request to upload file(s) to a Neocities website using the
 /// following endpoint: `/api/upload`
@@ -22,7 +30,9 @@ pub struct UploadResponse {
 }
 
 impl NcUpload {
-    fn request_info(args: Vec<String>) -> Result<HttpRequestInfo, NeocitiesErr> {
+    /// Prepares the information for a request to `/api/upload`, including the credentials found in
+    /// the environment, without sending it.
+    pub fn request_info(args: Vec<String>) -> Result<HttpRequestInfo, NeocitiesErr> {
         let cred = Credentials::new();
         let uri: String;
         let api_key: Option<String>;
@@ -64,6 +74,39 @@ impl NcUpload {
         let ur = NcUpload::to_upload_response(res)?;
         Ok(ur)
     }
+
+    /// Uploads each local file, given first in a pair, to the remote path given second, rather than
+    /// to the path the file has locally. It awaits a response and returns either a UploadResponse
+    /// or an error.
+    pub fn fetch_as(files: Vec<(String, String)>) -> Result<UploadResponse, NeocitiesErr> {
+        let req_info = NcUpload::request_info(Vec::new())?;
+        let res = post_request_multipart_as(req_info.uri, req_info.api_key, files)?;
+        let ur = NcUpload::to_upload_response(res)?;
+        Ok(ur)
+    }
+
+    /// Sends a request for each batch of files, with at most `jobs` requests in flight at once over
+    /// a shared connection, and at most one request started every `REQUEST_INTERVAL`. `on_done` is
+    /// called with the index of each batch and its UploadResponse, or error, as it arrives. If a
+    /// `Transfer` is given, it watches the files as they are sent.
+    pub fn fetch_all(
+        batches: Vec<Vec<String>>,
+        jobs: usize,
+        transfer: Option<Arc<Transfer>>,
+        on_done: &mut dyn FnMut(usize, Result<UploadResponse, NeocitiesErr>),
+    ) -> Result<(), NeocitiesErr> {
+        let req_info = NcUpload::request_info(Vec::new())?;
+        post_requests_multipart(
+            req_info.uri,
+            req_info.api_key,
+            batches,
+            jobs,
+            REQUEST_INTERVAL,
+            transfer,
+            &mut |i, res| on_done(i, res.and_then(NcUpload::to_upload_response)),
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
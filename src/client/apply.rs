@@ -0,0 +1,298 @@
+use super::{
+    command::Executable,
+    diff::Diff,
+    filetype,
+    journal::{JournalEntry, JournalFile},
+    output::Format,
+    plan::{PlanEntry, PlanFile},
+    trash,
+    upload::{Upload, UploadSession, DEFAULT_BATCH_SIZE},
+    usage::Limits,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        delete::NcDelete,
+        list::{ListResponse, NcList},
+        upload::NcUpload,
+    },
+    error::NeocitiesErr,
+};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "apply";
+
+/// Carries out a plan written by the `plan` command. The plan is refused if the remote website,
+/// or any of the local files to be uploaded, changed since the plan was created.
+pub struct Apply {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+impl Apply {
+    /// A constructor that returns an instance of `Apply`.
+    pub fn new() -> Apply {
+        Apply {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m <plan-file>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the paths whose remote state differs from the plan's baseline.
+    fn remote_drift(&self, baseline: &[PlanEntry], current: &[PlanEntry]) -> Vec<String> {
+        let before: BTreeSet<&PlanEntry> = baseline.iter().collect();
+        let after: BTreeSet<&PlanEntry> = current.iter().collect();
+
+        let paths: BTreeSet<String> = before
+            .symmetric_difference(&after)
+            .map(|e| e.path.clone())
+            .collect();
+
+        paths.into_iter().collect()
+    }
+
+    /// Returns the paths of files to be uploaded whose content no longer matches the plan.
+    fn local_drift(&self, uploads: &[PlanEntry]) -> Vec<String> {
+        let diff = Diff::new();
+        let mut changed: Vec<String> = Vec::new();
+
+        for entry in uploads.iter() {
+            let path = PathBuf::from(&entry.path);
+            let matches = match diff.hash(&path) {
+                Ok(h) => Some(h) == entry.sha1_hash,
+                Err(_) => false,
+            };
+
+            if !matches {
+                changed.push(entry.path.clone());
+            }
+        }
+
+        changed
+    }
+
+    /// Confirms that the plan still describes the current local and remote state, and that
+    /// Neocities accepts every file it uploads. It returns the current listing of the website.
+    fn check(
+        &self,
+        plan: &PlanFile,
+        limits: &Limits,
+        mut writer: impl std::io::Write,
+    ) -> Result<ListResponse, NeocitiesErr> {
+        let list = NcList::fetch(None)?;
+        let current = PlanFile::baseline_of(&PathBuf::from(&plan.local_dir), &list)?;
+
+        let remote = self.remote_drift(&plan.baseline, &current);
+        let local = self.local_drift(&plan.uploads);
+
+        for path in remote.iter() {
+            let output = format!("\x1b[93mremote changed\x1b[0m: {path}\n");
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        for path in local.iter() {
+            let output = format!("\x1b[93mlocal changed\x1b[0m: {path}\n");
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        if !remote.is_empty() || !local.is_empty() {
+            return Err(NeocitiesErr::StalePlan);
+        }
+
+        // every upload is checked before anything is sent, like `upload` does
+        let upload = Upload::new();
+        let paths: Vec<String> = plan.uploads.iter().map(|e| e.path.clone()).collect();
+        let rejected = upload.validate(&paths, filetype::is_supporter(), limits.max_file_size)?;
+        if !rejected.is_empty() {
+            upload.write_rejected(&rejected, Format::Text, &mut writer)?;
+            return Err(NeocitiesErr::DisallowedFileType);
+        }
+
+        Ok(list)
+    }
+}
+
+/// Describes the deletes of a plan for the journal
+fn journal_files(entries: &[PlanEntry]) -> Vec<JournalFile> {
+    entries
+        .iter()
+        .map(|e| JournalFile {
+            path: e.path.clone(),
+            size: e.size,
+            sha1_hash: e.sha1_hash.clone(),
+        })
+        .collect()
+}
+
+impl Default for Apply {
+    fn default() -> Apply {
+        Apply::new()
+    }
+}
+
+impl Executable for Apply {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        if args.len() != 1 {
+            let output = format!("{}\nusage: {}\n", self.get_long_desc(), self.get_usage());
+            self.write(output.as_str(), &mut stdout)?;
+            return Ok(());
+        }
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let plan = PlanFile::read(Path::new(&args[0]))?;
+
+        if plan.uploads.is_empty() && plan.deletes.is_empty() {
+            self.write("Nothing to apply\n", &mut stdout)?;
+            return Ok(());
+        }
+
+        let limits = Limits::new(filetype::is_supporter(), None, None)?;
+        let list = self.check(&plan, &limits, &mut stdout)?;
+
+        if !plan.uploads.is_empty() {
+            let upload = Upload::new();
+            let paths: Vec<String> = plan.uploads.iter().map(|e| e.path.clone()).collect();
+            upload.check_quota(&list, &paths, &limits, std::io::stderr())?;
+
+            // the uploads are tracked like any other, so that `upload --resume` can send the
+            // files that fail
+            let mut session = UploadSession::new(paths.clone());
+            let send = |batches, jobs, on_done: &mut dyn FnMut(usize, _)| {
+                NcUpload::fetch_all(batches, jobs, None, on_done)
+            };
+            upload.upload_batches(KEY, DEFAULT_BATCH_SIZE, 1, &mut session, send, &mut stdout)?;
+
+            // nothing is deleted unless every file landed, since a delete may remove a file
+            // that a failed upload was meant to replace
+            if !session.failed.is_empty() {
+                upload.write_failures(&session, &mut stdout)?;
+                return Err(NeocitiesErr::UploadFailed);
+            }
+            UploadSession::remove()?;
+        }
+
+        if !plan.deletes.is_empty() {
+            let paths: Vec<String> = plan.deletes.iter().map(|e| e.path.clone()).collect();
+            let data = NcDelete::fetch(paths)?;
+            JournalEntry::new(
+                "delete",
+                KEY,
+                trash::sitename().ok(),
+                journal_files(&plan.deletes),
+            )
+            .respond(&data.result, data.error_type.clone(), &data.message)
+            .append()?;
+            let output = format!(
+                "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                data.result, data.message
+            );
+            self.write(output.as_str(), &mut stdout)?;
+
+            if data.result != "success" {
+                return Err(NeocitiesErr::RequestFailed);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Carry out a plan written by `plan`. The plan is refused if your \
+Neocities website, or any file it uploads, changed since the plan was created. Uploads are \
+sent in batches and checked like `upload` checks them; if any file fails, nothing is deleted and \
+`upload --resume` retries the failed files. Run it from the directory the plan was created in.";
+
+const DESC_SHORT: &str = "Apply a deployment plan";
+
+#[cfg(test)]
+mod tests {
+    use super::{Apply, DESC, DESC_SHORT, KEY};
+    use crate::{
+        client::{command::Executable, plan::PlanEntry},
+        error::NeocitiesErr,
+    };
+    use std::fs;
+
+    #[test]
+    fn usage_desc_methods() {
+        let a = Apply::new();
+        assert_eq!(a.get_usage().contains(KEY), true);
+        assert_eq!(a.get_short_desc(), DESC_SHORT);
+        assert_eq!(a.get_long_desc(), DESC);
+    }
+
+    fn entry(path: &str, sha1_hash: &str) -> PlanEntry {
+        PlanEntry {
+            path: path.to_string(),
+            is_directory: false,
+            size: Some(1),
+            sha1_hash: Some(sha1_hash.to_string()),
+        }
+    }
+
+    #[test]
+    fn remote_drift_method() {
+        let a = Apply::new();
+
+        let baseline = vec![entry("site/a.html", "a"), entry("site/b.html", "b")];
+
+        assert_eq!(a.remote_drift(&baseline, &baseline.clone()).len(), 0);
+
+        let current = vec![
+            entry("site/a.html", "a"),
+            entry("site/b.html", "changed"),
+            entry("site/c.html", "c"),
+        ];
+        assert_eq!(
+            a.remote_drift(&baseline, &current),
+            vec!["site/b.html".to_string(), "site/c.html".to_string()]
+        );
+    }
+
+    #[test]
+    fn local_drift_method() -> Result<(), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("test_file.txt");
+        fs::write(&path, "Hello, World!")?;
+        let path = path.to_str().unwrap();
+
+        let a = Apply::new();
+
+        let unchanged = vec![entry(path, "0a0a9f2a6772942557ab5355d76af442f8f65e01")];
+        assert_eq!(a.local_drift(&unchanged).len(), 0);
+
+        let changed = vec![entry(path, "foo"), entry("nonexistent.html", "bar")];
+        assert_eq!(a.local_drift(&changed).len(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use super::{
+    command::Executable,
+    trash::{public_url, sitename},
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        http::stream_file,
+    },
+    error::NeocitiesErr,
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "cat";
+
+/// Prints the live contents of files on a Neocities user's website
+pub struct Cat {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+impl Cat {
+    /// A constructor that returns an instance of `Cat`.
+    pub fn new() -> Cat {
+        Cat {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m <path> [<path> ...]"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the public URL of each path, in order. The root of the website is not a file, so
+    /// an empty path is refused.
+    fn urls(&self, paths: &[String], site: &str) -> Result<Vec<String>, NeocitiesErr> {
+        let mut urls: Vec<String> = Vec::new();
+        for path in paths.iter() {
+            if path.starts_with('-') || path.trim_matches('/').is_empty() {
+                return Err(NeocitiesErr::InvalidArgument);
+            }
+            urls.push(public_url(site, path));
+        }
+        Ok(urls)
+    }
+}
+
+impl Default for Cat {
+    fn default() -> Cat {
+        Cat::new()
+    }
+}
+
+impl Executable for Cat {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        if args.is_empty() {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let urls = self.urls(&args, &sitename()?)?;
+        for url in urls.into_iter() {
+            stream_file(url, &mut stdout)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Print the contents of one or more files on your Neocities website, as it \
+serves them right now, to stdout. Files are fetched from the public URL of your site and written \
+as they arrive, so `cat` works for images and other binary files too (e.g. `neocities_cli cat \
+logo.png > logo.png`).";
+
+const DESC_SHORT: &str = "Print remote files to stdout";
+
+#[cfg(test)]
+mod tests {
+    use super::{Cat, DESC, DESC_SHORT, KEY};
+    use crate::{client::command::Executable, error::NeocitiesErr};
+
+    #[test]
+    fn usage_desc_methods() {
+        let c = Cat::new();
+        assert_eq!(c.get_usage().contains(KEY), true);
+        assert_eq!(c.get_short_desc(), DESC_SHORT);
+        assert_eq!(c.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn urls_method() -> Result<(), NeocitiesErr> {
+        let c = Cat::new();
+
+        let urls = c.urls(
+            &[
+                String::from("/index.html"),
+                String::from("css/my style.css"),
+            ],
+            "foo",
+        )?;
+        assert_eq!(
+            urls,
+            vec![
+                "https://foo.neocities.org/index.html",
+                "https://foo.neocities.org/css/my%20style.css"
+            ]
+        );
+
+        assert_eq!(c.urls(&[String::from("/")], "foo").is_err(), true);
+        assert_eq!(c.urls(&[String::from("--json")], "foo").is_err(), true);
+
+        Ok(())
+    }
+}
@@ -20,6 +20,36 @@ pub enum CommandKind {
     Key,
     /// Corresponds to the `diff` module
     Diff,
+    /// Corresponds to the `mv` module
+    Move,
+    /// Corresponds to the `plan` module
+    Plan,
+    /// Corresponds to the `apply` module
+    Apply,
+    /// Corresponds to the `trash` module
+    Trash,
+    /// Corresponds to the `log` module
+    Log,
+    /// Corresponds to the `rollback` module
+    Rollback,
+    /// Corresponds to the `usage` module
+    Usage,
+    /// Corresponds to the `find` module
+    Find,
+    /// Corresponds to the `du` module
+    Du,
+    /// Corresponds to the `stat` module
+    Stat,
+    /// Corresponds to the `cat` module
+    Cat,
+    /// Corresponds to the `edit` module
+    Edit,
+    /// Corresponds to the `watch` module
+    Watch,
+    /// Corresponds to the `serve` module
+    Serve,
+    /// Corresponds to the `init` module
+    Init,
 }
 
 /// Defines shared behavior among command kinds
@@ -52,6 +82,21 @@ impl Command {
             CommandKind::Delete => Box::new(delete::Delete::new()),
             CommandKind::Key => Box::new(key::Key::new()),
             CommandKind::Diff => Box::new(diff::Diff::new()),
+            CommandKind::Move => Box::new(mv::Move::new()),
+            CommandKind::Plan => Box::new(plan::Plan::new()),
+            CommandKind::Apply => Box::new(apply::Apply::new()),
+            CommandKind::Trash => Box::new(trash::Trash::new()),
+            CommandKind::Log => Box::new(log::Log::new()),
+            CommandKind::Rollback => Box::new(rollback::Rollback::new()),
+            CommandKind::Usage => Box::new(usage::Usage::new()),
+            CommandKind::Find => Box::new(find::Find::new()),
+            CommandKind::Du => Box::new(du::Du::new()),
+            CommandKind::Stat => Box::new(stat::Stat::new()),
+            CommandKind::Cat => Box::new(cat::Cat::new()),
+            CommandKind::Edit => Box::new(edit::Edit::new()),
+            CommandKind::Watch => Box::new(watch::Watch::new()),
+            CommandKind::Serve => Box::new(serve::Serve::new()),
+            CommandKind::Init => Box::new(init::Init::new()),
         };
 
         Command { exec }
@@ -1,7 +1,10 @@
 use super::{
+    apply,
     args::Args,
+    cat,
     command::{Command, CommandKind},
-    delete, diff, info, key, list, upload, version,
+    delete, diff, du, edit, find, info, init, key, list, log, mv, plan, rollback, serve, stat,
+    trash, upload, usage, version, watch,
 };
 use crate::error::NeocitiesErr;
 
@@ -30,6 +33,21 @@ impl Config {
                 info::KEY => Command::new(CommandKind::Info),
                 key::KEY => Command::new(CommandKind::Key),
                 diff::KEY => Command::new(CommandKind::Diff),
+                mv::KEY => Command::new(CommandKind::Move),
+                plan::KEY => Command::new(CommandKind::Plan),
+                apply::KEY => Command::new(CommandKind::Apply),
+                trash::KEY => Command::new(CommandKind::Trash),
+                log::KEY => Command::new(CommandKind::Log),
+                rollback::KEY => Command::new(CommandKind::Rollback),
+                usage::KEY => Command::new(CommandKind::Usage),
+                find::KEY => Command::new(CommandKind::Find),
+                du::KEY => Command::new(CommandKind::Du),
+                stat::KEY => Command::new(CommandKind::Stat),
+                cat::KEY => Command::new(CommandKind::Cat),
+                edit::KEY => Command::new(CommandKind::Edit),
+                watch::KEY => Command::new(CommandKind::Watch),
+                serve::KEY => Command::new(CommandKind::Serve),
+                init::KEY => Command::new(CommandKind::Init),
                 _ => Command::new(CommandKind::Help),
             },
             _ => Command::new(CommandKind::Help),
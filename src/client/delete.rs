@@ -1,16 +1,33 @@
-use super::command::Executable;
+use super::{
+    command::Executable,
+    journal::{JournalEntry, JournalFile},
+    output::{FileReport, Format, Report},
+    trash::{self, TrashEntry},
+};
 use crate::{
     api::{
         credentials::{Credentials, ENV_VAR_MSG},
         delete::NcDelete,
+        http::get_file,
+        list::{File, ListResponse, NcList},
     },
     error::NeocitiesErr,
 };
-use std::io;
+use glob::{MatchOptions, Pattern};
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal},
+};
 
 /// The string literal a user must type to run functionality in this module
 pub const KEY: &'static str = "delete";
 
+/// Paths that are only deleted when `--allow-protected` is given
+pub const PROTECTED_PATHS: [&str; 2] = ["index.html", "404.html"];
+
+/// The environment variable that replaces the default protected paths with a comma-separated list
+pub const ENV_PROTECTED: &str = "NEOCITIES_PROTECTED";
+
 /// Deletes files from a Neocities user's site. The Neocities API allows a user to delete any files
 /// except for `index.html`
 pub struct Delete {
@@ -19,12 +36,36 @@ pub struct Delete {
     long: String,
 }
 
+/// Options that alter how files are deleted
+struct DeleteOpts {
+    /// Prepare the request and report what would be sent, without sending it
+    dry_run: bool,
+    /// The format in which the results are written to stdout
+    format: Format,
+    /// Delete directories along with everything inside them
+    recursive: bool,
+    /// Skip the confirmation prompt
+    yes: bool,
+    /// Permit the deletion of protected paths
+    allow_protected: bool,
+    /// Save a copy of each file to the local trash before deleting it
+    trash: bool,
+}
+
+/// The remote entries that the arguments of a delete command resolve to
+struct Targets {
+    /// Every file and directory that will be deleted, sorted by path
+    entries: Vec<File>,
+    /// Literal paths that were not found on the site
+    missing: Vec<String>,
+}
+
 impl Delete {
     /// A constructor that returns an instance of `Delete`.
     pub fn new() -> Delete {
         Delete {
             usage: String::from(format!(
-                "\x1b[1;32m{KEY}\x1b[0m <filename> [<another filename>]"
+                "\x1b[1;32m{KEY}\x1b[0m [-r] [--yes] [--allow-protected] [--trash] [--dry-run] [--output <text|json>] <path|glob> [<another path|glob>]"
             )),
             short: String::from(DESC_SHORT),
             long: String::from(DESC),
@@ -36,9 +77,227 @@ impl Delete {
         Ok(())
     }
 
+    fn parse_opts(&self, args: Vec<String>) -> Result<(DeleteOpts, Vec<String>), NeocitiesErr> {
+        let mut opts = DeleteOpts {
+            dry_run: false,
+            format: Format::Text,
+            recursive: false,
+            yes: false,
+            allow_protected: false,
+            trash: false,
+        };
+        let mut rest: Vec<String> = Vec::new();
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                opts.format = format;
+                i += consumed;
+                continue;
+            }
+
+            match args[i].as_str() {
+                "-n" | "--dry-run" => opts.dry_run = true,
+                "-r" | "--recursive" => opts.recursive = true,
+                "-y" | "--yes" | "-f" | "--force" => opts.yes = true,
+                "--allow-protected" => opts.allow_protected = true,
+                "-t" | "--trash" => opts.trash = true,
+                _ => rest.push(args[i].clone()),
+            }
+            i += 1;
+        }
+
+        Ok((opts, rest))
+    }
+
+    /// Resolves the arguments of a delete command against the remote listing. Globs (`*`, `?`,
+    /// `[...]`, `**`) are matched against every remote path. A directory is only deleted with
+    /// `recursive`, in which case everything inside it is deleted too; without it, a directory
+    /// given by name is an error and a directory matched by a glob is skipped, unless the glob
+    /// also matches everything inside it.
+    fn resolve(
+        &self,
+        args: &[String],
+        remote: &ListResponse,
+        recursive: bool,
+    ) -> Result<Targets, NeocitiesErr> {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        let mut paths: Vec<String> = Vec::new();
+        let mut missing: Vec<String> = Vec::new();
+
+        for arg in args.iter() {
+            let target = arg.trim_matches('/');
+            if target.is_empty() {
+                return Err(NeocitiesErr::InvalidPath);
+            }
+
+            if !is_glob(target) {
+                match remote.files.iter().find(|f| f.path == target) {
+                    Some(f) if f.is_directory && !recursive => {
+                        return Err(NeocitiesErr::IsDirectory)
+                    }
+                    Some(f) => paths.push(f.path.clone()),
+                    None => missing.push(target.to_string()),
+                }
+                continue;
+            }
+
+            let pattern = match Pattern::new(target) {
+                Ok(p) => p,
+                Err(_) => return Err(NeocitiesErr::InvalidArgument),
+            };
+            let matches = |f: &File| pattern.matches_with(&f.path, options);
+
+            let mut matched = false;
+            for f in remote.files.iter().filter(|f| matches(f)) {
+                if f.is_directory && !recursive {
+                    let prefix = format!("{}/", f.path);
+                    let emptied = remote
+                        .files
+                        .iter()
+                        .filter(|c| c.path.starts_with(&prefix))
+                        .all(matches);
+                    if !emptied {
+                        continue;
+                    }
+                }
+                matched = true;
+                paths.push(f.path.clone());
+            }
+
+            if !matched {
+                missing.push(target.to_string());
+            }
+        }
+
+        // everything inside a targeted directory goes with it
+        let dirs: Vec<String> = remote
+            .files
+            .iter()
+            .filter(|f| f.is_directory && paths.contains(&f.path))
+            .map(|f| format!("{}/", f.path))
+            .collect();
+
+        let mut entries: Vec<File> = remote
+            .files
+            .iter()
+            .filter(|f| paths.contains(&f.path) || dirs.iter().any(|d| f.path.starts_with(d)))
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries.dedup_by(|a, b| a.path == b.path);
+
+        Ok(Targets { entries, missing })
+    }
+
+    /// Returns the paths to send in the request. Entries inside a directory that is itself being
+    /// deleted are left out, since the server removes them along with the directory.
+    fn request_paths(&self, entries: &[File]) -> Vec<String> {
+        let dirs: Vec<String> = entries
+            .iter()
+            .filter(|f| f.is_directory)
+            .map(|f| format!("{}/", f.path))
+            .collect();
+
+        entries
+            .iter()
+            .filter(|f| !dirs.iter().any(|d| f.path.starts_with(d)))
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    /// Returns the protected paths: the list in `NEOCITIES_PROTECTED` if it is set, otherwise
+    /// `PROTECTED_PATHS`.
+    fn protected_paths(&self) -> Vec<String> {
+        match env::var(ENV_PROTECTED) {
+            Ok(list) => list
+                .split(',')
+                .map(|p| p.trim().trim_matches('/').to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            Err(_) => PROTECTED_PATHS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Returns the entries to be deleted that are protected, including those inside a directory
+    /// that is being deleted.
+    fn find_protected(&self, entries: &[File], protected: &[String]) -> Vec<String> {
+        entries
+            .iter()
+            .filter(|f| protected.contains(&f.path))
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    /// Describes each entry to be deleted, followed by each path that was not found.
+    fn file_reports(&self, targets: &Targets) -> Vec<FileReport> {
+        let mut reports: Vec<FileReport> = targets
+            .entries
+            .iter()
+            .map(|f| FileReport {
+                path: display_path(f),
+                size: f.size,
+                sha1_hash: f.sha1_hash.clone(),
+                status: Some(String::from("found")),
+                message: None,
+            })
+            .collect();
+
+        for path in targets.missing.iter() {
+            reports.push(FileReport {
+                path: path.clone(),
+                size: None,
+                sha1_hash: None,
+                status: Some(String::from("missing")),
+                message: None,
+            });
+        }
+
+        reports
+    }
+
+    fn write_dry_run(
+        &self,
+        report: &Report,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        self.write(
+            "\x1b[93mDry run\x1b[0m: nothing will be sent. The following files would be deleted:\n",
+            &mut writer,
+        )?;
+
+        for file in report.files.iter() {
+            let output = match file.status.as_deref() {
+                Some("missing") => format!(
+                    "  \x1b[92m{}\x1b[0m \x1b[91m(not found on your site)\x1b[0m\n",
+                    file.path
+                ),
+                _ => match file.size {
+                    Some(size) => format!("  \x1b[92m{}\x1b[0m ({} bytes)\n", file.path, size),
+                    None => format!("  \x1b[92m{}\x1b[0m\n", file.path),
+                },
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        let output = format!(
+            "Total: {} file(s), {} bytes\n",
+            report.files.len(),
+            report.total_bytes
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
+
     fn alert_warn(
         &self,
         args: Vec<String>,
+        mut reader: impl BufRead,
         mut writer: impl std::io::Write,
     ) -> Result<bool, NeocitiesErr> {
         let warn = "\x1b[93mWarning.\x1b[0m Are you sure you want to delete the following files?\n";
@@ -56,7 +315,11 @@ impl Delete {
         loop {
             let mut input = String::new();
 
-            io::stdin().read_line(&mut input)?;
+            // stdin was closed before an answer was given
+            if reader.read_line(&mut input)? == 0 {
+                self.write("No input. Canceling delete operation.\n", &mut writer)?;
+                break;
+            }
 
             let input = input.trim();
 
@@ -81,6 +344,20 @@ impl Delete {
     }
 }
 
+/// Returns true if a path contains any of the special characters of a glob pattern
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Returns the path of a remote entry as it is shown to the user, with a trailing slash on
+/// directories
+fn display_path(file: &File) -> String {
+    match file.is_directory {
+        true => format!("{}/", file.path),
+        false => file.path.clone(),
+    }
+}
+
 impl Executable for Delete {
     fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
         let mut stdout = std::io::stdout();
@@ -96,15 +373,105 @@ impl Executable for Delete {
             return Ok(());
         }
 
-        let cancel = self.alert_warn(args[..].to_vec(), &mut stdout)?;
+        let (opts, args) = self.parse_opts(args)?;
+        if args.is_empty() {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
 
-        if cancel == false {
-            let data = NcDelete::fetch(args)?;
-            let output = format!(
-                "\x1b[93mStatus\x1b[0m: {} - {}\n",
-                data.result, data.message
-            );
-            self.write(output.as_str(), &mut stdout)?;
+        let list = NcList::fetch(None)?;
+        let targets = self.resolve(&args, &list, opts.recursive)?;
+        let paths = self.request_paths(&targets.entries);
+
+        if !opts.allow_protected {
+            let protected = self.find_protected(&targets.entries, &self.protected_paths());
+            if !protected.is_empty() {
+                for path in protected.iter() {
+                    let output = format!("\x1b[91mprotected\x1b[0m: {path}\n");
+                    self.write(output.as_str(), io::stderr())?;
+                }
+                return Err(NeocitiesErr::ProtectedPath);
+            }
+        }
+
+        if opts.dry_run {
+            // resolve the credentials the request would use, without sending it
+            NcDelete::request_info(paths)?;
+
+            let report = Report::new(KEY, true, self.file_reports(&targets));
+
+            match opts.format {
+                Format::Json => report.write_json(&mut stdout)?,
+                _ => self.write_dry_run(&report, &mut stdout)?,
+            }
+            return Ok(());
+        }
+
+        if !targets.missing.is_empty() {
+            for path in targets.missing.iter() {
+                let output = format!("\x1b[91mnot found\x1b[0m: {path}\n");
+                self.write(output.as_str(), io::stderr())?;
+            }
+            return Err(NeocitiesErr::MissingFile);
+        }
+
+        // every concrete file is listed, even when only its directory is sent
+        let listed: Vec<String> = targets.entries.iter().map(display_path).collect();
+
+        // without a terminal there is nobody to answer the prompt
+        let stdin = io::stdin();
+        if !opts.yes && !stdin.is_terminal() {
+            return Err(NeocitiesErr::NotInteractive);
+        }
+
+        // keep stdout free for the JSON report
+        let cancel = match (opts.yes, &opts.format) {
+            (true, _) => false,
+            (false, Format::Json) => self.alert_warn(listed, stdin.lock(), io::stderr())?,
+            (false, _) => self.alert_warn(listed, stdin.lock(), &mut stdout)?,
+        };
+
+        if !cancel {
+            if opts.trash {
+                let entry = TrashEntry::collect(&trash::sitename()?, &targets.entries, get_file)?;
+                let output = format!(
+                    "Saved {} file(s) to the trash as \x1b[1;93m{}\x1b[0m\n",
+                    entry.files.len(),
+                    entry.id
+                );
+                match opts.format {
+                    Format::Json => self.write(output.as_str(), io::stderr())?,
+                    _ => self.write(output.as_str(), &mut stdout)?,
+                }
+            }
+
+            let mut report = Report::new(KEY, false, self.file_reports(&targets));
+            let data = NcDelete::fetch(paths)?;
+            let files = targets.entries.iter().map(JournalFile::from).collect();
+            JournalEntry::new("delete", KEY, trash::sitename().ok(), files)
+                .respond(&data.result, data.error_type.clone(), &data.message)
+                .append()?;
+
+            let failed = data.result != "success";
+
+            match opts.format {
+                Format::Json => {
+                    report.result = Some(data.result);
+                    report.error_type = data.error_type;
+                    report.message = Some(data.message);
+                    report.write_json(&mut stdout)?;
+                }
+                _ => {
+                    let output = format!(
+                        "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                        data.result, data.message
+                    );
+                    self.write(output.as_str(), &mut stdout)?;
+                }
+            }
+
+            if failed {
+                return Err(NeocitiesErr::RequestFailed);
+            }
         }
 
         Ok(())
@@ -123,13 +490,29 @@ impl Executable for Delete {
     }
 }
 
-const DESC: &'static str = "Delete files from your Neocities website";
+const DESC: &'static str = "Delete files from your Neocities website. Paths may be globs, such \
+as 'images/*.png' or 'drafts/**' (quote them so the shell does not expand them), and are matched \
+against the files on your site. Use `-r` to delete directories and everything inside them. \
+Deletion asks for confirmation; pass `--yes` (or `--force`) to skip it, which is required when \
+stdin is not a terminal. Protected paths (index.html and 404.html, or the comma-separated list in \
+NEOCITIES_PROTECTED) are only deleted with `--allow-protected`. Use \
+`--trash` to download a copy of each file from your public site before it is deleted, so that it \
+can be brought back with `trash restore`. Use \
+`--dry-run` to see what would be deleted without sending the request, and `--output json` for a \
+JSON report.";
 
 const DESC_SHORT: &'static str = "Delete files from Neocities";
 
 #[cfg(test)]
 mod tests {
-    use crate::{client::command::Executable, error::NeocitiesErr};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{
+            command::Executable,
+            output::{Format, Report},
+        },
+        error::NeocitiesErr,
+    };
 
     use super::{Delete, DESC, DESC_SHORT, KEY};
 
@@ -163,4 +546,210 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+        let args: Vec<String> = vec!["foo.html", "--dry-run", "--json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let (opts, rest) = d.parse_opts(args)?;
+        assert_eq!(opts.dry_run, true);
+        assert_eq!(opts.format, Format::Json);
+        assert_eq!(rest, vec!["foo.html".to_string()]);
+
+        Ok(())
+    }
+
+    fn file(path: &str, is_directory: bool, size: Option<i64>) -> File {
+        File {
+            path: String::from(path),
+            is_directory,
+            size,
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: size.map(|_| String::from("foo")),
+        }
+    }
+
+    fn remote() -> ListResponse {
+        ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("foo.html", false, Some(10)),
+                file("images", true, None),
+                file("images/a.png", false, Some(100)),
+                file("images/b.png", false, Some(50)),
+                file("images/c.jpg", false, Some(20)),
+                file("drafts", true, None),
+                file("drafts/one.html", false, Some(1)),
+                file("drafts/old", true, None),
+                file("drafts/old/two.html", false, Some(2)),
+            ],
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn paths(files: &[File]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn resolve_method_literal_paths() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+        let lr = remote();
+
+        let t = d.resolve(&strings(&["/foo.html", "bar.html"]), &lr, false)?;
+        assert_eq!(paths(&t.entries), vec!["foo.html"]);
+        assert_eq!(t.missing, vec!["bar.html".to_string()]);
+
+        match d.resolve(&strings(&["images"]), &lr, false) {
+            Err(NeocitiesErr::IsDirectory) => {}
+            _ => panic!("expected a directory error"),
+        }
+
+        let t = d.resolve(&strings(&["images/"]), &lr, true)?;
+        assert_eq!(
+            paths(&t.entries),
+            vec!["images", "images/a.png", "images/b.png", "images/c.jpg"]
+        );
+        assert_eq!(d.request_paths(&t.entries), vec!["images".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_method_globs() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+        let lr = remote();
+
+        let t = d.resolve(&strings(&["images/*.png", "foo.html"]), &lr, false)?;
+        assert_eq!(
+            paths(&t.entries),
+            vec!["foo.html", "images/a.png", "images/b.png"]
+        );
+        assert_eq!(
+            d.request_paths(&t.entries),
+            strings(&["foo.html", "images/a.png", "images/b.png"])
+        );
+
+        // a single star does not cross directories
+        let t = d.resolve(&strings(&["*.png"]), &lr, false)?;
+        assert_eq!(t.entries.is_empty(), true);
+        assert_eq!(t.missing, vec!["*.png".to_string()]);
+
+        // `drafts/old` is emptied by the glob, so it goes too
+        let t = d.resolve(&strings(&["drafts/**"]), &lr, false)?;
+        assert_eq!(
+            paths(&t.entries),
+            vec!["drafts/old", "drafts/old/two.html", "drafts/one.html"]
+        );
+        assert_eq!(
+            d.request_paths(&t.entries),
+            strings(&["drafts/old", "drafts/one.html"])
+        );
+
+        // a directory matched by a glob is skipped unless it is emptied or `-r` is given
+        let t = d.resolve(&strings(&["*"]), &lr, false)?;
+        assert_eq!(paths(&t.entries), vec!["foo.html"]);
+        let t = d.resolve(&strings(&["*"]), &lr, true)?;
+        assert_eq!(t.entries.len(), lr.files.len());
+        assert_eq!(
+            d.request_paths(&t.entries),
+            strings(&["drafts", "foo.html", "images"])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_reports_method() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+        let lr = remote();
+
+        let t = d.resolve(&strings(&["foo.html", "images", "bar.html"]), &lr, true)?;
+        let reports = d.file_reports(&t);
+        assert_eq!(reports[0].path, "foo.html");
+        assert_eq!(reports[0].size, Some(10));
+        assert_eq!(reports[0].status.as_deref(), Some("found"));
+        assert_eq!(reports[1].path, "images/");
+        assert_eq!(reports[1].size, None);
+        assert_eq!(reports[5].path, "bar.html");
+        assert_eq!(reports[5].status.as_deref(), Some("missing"));
+
+        let report = Report::new(KEY, true, reports);
+        assert_eq!(report.total_bytes, 180);
+
+        let mut output = Vec::new();
+        d.write_dry_run(&report, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("Dry run"), true);
+        assert_eq!(s.contains("images/c.jpg"), true);
+        assert_eq!(s.contains("not found"), true);
+        assert_eq!(s.contains("180 bytes"), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_opts_method_confirmation_flags() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+
+        let (opts, rest) =
+            d.parse_opts(strings(&["--force", "index.html", "--allow-protected"]))?;
+        assert_eq!(opts.yes, true);
+        assert_eq!(opts.allow_protected, true);
+        assert_eq!(rest, strings(&["index.html"]));
+
+        let (opts, _) = d.parse_opts(strings(&["-y", "foo.html"]))?;
+        assert_eq!(opts.yes, true);
+        assert_eq!(opts.allow_protected, false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_protected_method() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+        let mut lr = remote();
+        lr.files.push(file("index.html", false, Some(5)));
+        lr.files.push(file("drafts/404.html", false, Some(5)));
+
+        let protected = strings(&["index.html", "drafts/404.html"]);
+
+        let t = d.resolve(&strings(&["foo.html"]), &lr, false)?;
+        assert_eq!(d.find_protected(&t.entries, &protected).is_empty(), true);
+
+        // a protected file inside a directory being deleted is caught too
+        let t = d.resolve(&strings(&["*"]), &lr, true)?;
+        assert_eq!(
+            d.find_protected(&t.entries, &protected),
+            strings(&["drafts/404.html", "index.html"])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn alert_warn_method() -> Result<(), NeocitiesErr> {
+        let d = Delete::new();
+
+        let mut output = Vec::new();
+        let cancel = d.alert_warn(strings(&["foo.html"]), &b"maybe\ny\n"[..], &mut output)?;
+        assert_eq!(cancel, false);
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("foo.html"), true);
+        assert_eq!(s.contains("Invalid input: 'maybe'"), true);
+
+        // a closed stdin cancels instead of looping
+        let mut output = Vec::new();
+        let cancel = d.alert_warn(strings(&["foo.html"]), &b""[..], &mut output)?;
+        assert_eq!(cancel, true);
+
+        Ok(())
+    }
 }
@@ -1,12 +1,16 @@
-use super::command::Executable;
+use super::{command::Executable, ignore::Ignore, output::Format, state};
 use crate::{
-    api::list::{File, ListResponse, NcList},
+    api::{
+        list::{File, ListResponse, NcList},
+        rename::{NcRename, RenameResponse},
+    },
     error::NeocitiesErr,
 };
 use chrono::{TimeZone, Utc};
+use serde_derive::Serialize;
 use sha1::{Digest, Sha1};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     format,
     fs::{self, read_dir},
     os::linux::fs::MetadataExt,
@@ -46,6 +50,106 @@ pub struct Item {
     /// A textual note associated with the item, providing information regarding differences
     /// between it and its local or remote counterpart.
     remark: String,
+
+    /// The kind of difference found for the item, or `None` if it has not been compared yet.
+    status: Option<Status>,
+}
+
+/// Classifies the difference found for a path, from the point of view of deploying the local
+/// path to the remote site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Status {
+    /// The item exists locally but not on the remote; a deployment would add it.
+    #[serde(rename = "A")]
+    Added,
+
+    /// The item exists in both places with different content, and the local copy is newer.
+    #[serde(rename = "M")]
+    Modified,
+
+    /// The item exists on the remote but not locally; a deployment would delete it.
+    #[serde(rename = "D")]
+    Deleted,
+
+    /// The item exists in both places with different content, but the remote copy is newer. This
+    /// usually means the live site was edited by hand.
+    #[serde(rename = "C")]
+    Conflict,
+
+    /// A remote file that is missing locally has the same content as a local file that is missing
+    /// on the remote; the file was renamed or moved.
+    #[serde(rename = "R")]
+    Renamed,
+}
+
+impl Item {
+    /// Returns the file data of the item.
+    pub(crate) fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+impl Status {
+    /// Returns the single letter code used for the status in porcelain output.
+    pub fn code(&self) -> char {
+        match self {
+            Status::Added => 'A',
+            Status::Modified => 'M',
+            Status::Deleted => 'D',
+            Status::Conflict => 'C',
+            Status::Renamed => 'R',
+        }
+    }
+}
+
+/// Summarizes the difference found for a single path, merging its local and remote items. This is
+/// the record written by the porcelain and JSON output formats.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    /// The kind of difference found for the path.
+    pub status: Status,
+
+    /// The path of the item, as it appears on the remote. For a renamed file, this is the new
+    /// path.
+    pub path: String,
+
+    /// The previous path of a renamed file, as it currently appears on the remote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Indicates whether the path is a directory.
+    pub is_directory: bool,
+
+    /// The SHA-1 hash of the local file, if it exists locally.
+    pub local_sha1: Option<String>,
+
+    /// The byte size of the local file, if it exists locally.
+    pub local_size: Option<i64>,
+
+    /// The SHA-1 hash of the remote file, if it exists on the remote.
+    pub remote_sha1: Option<String>,
+}
+
+/// Options that alter how the differences are reported.
+pub struct DiffOpts {
+    /// The format in which the differences are written to stdout.
+    format: Format,
+
+    /// Indicates whether the command should fail when local and remote are out of sync.
+    exit_code: bool,
+
+    /// Indicates whether detected renames should be carried out on the remote.
+    apply_renames: bool,
+}
+
+/// The JSON document written by `diff --output json`.
+#[derive(Serialize)]
+struct DiffReport<'a> {
+    /// Indicates whether the local and remote paths are in sync.
+    in_sync: bool,
+
+    /// The differences found between the local and remote paths.
+    changes: &'a [Change],
 }
 
 impl<'a> Diff<'a> {
@@ -62,7 +166,7 @@ impl<'a> Diff<'a> {
             // Short description.
             desc_short: DESC_SHORT,
             // Usage information with formatting.
-            usage: format!("\x1b[1;32m{KEY}\x1b[0m ./<path>"),
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m {USAGE_ARGS}"),
         }
     }
 
@@ -132,6 +236,48 @@ impl<'a> Diff<'a> {
         };
     }
 
+    /// Separates option flags from positional arguments.
+    ///
+    /// Recognized flags are `--output <text|porcelain|json>` (along with the `--porcelain` and
+    /// `--json` shorthands), `--exit-code` and `--apply-renames`. Any other argument is returned as
+    /// a positional argument, in its original order.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the parsed `DiffOpts` and the remaining arguments, or an error
+    /// of type `NeocitiesErr` if a flag has an invalid value.
+    fn parse_opts(&self, args: Vec<String>) -> Result<(DiffOpts, Vec<String>), NeocitiesErr> {
+        // Start with the default options: human readable output, exit code zero.
+        let mut opts = DiffOpts {
+            format: Format::Text,
+            exit_code: false,
+            apply_renames: false,
+        };
+
+        // Collect the arguments that are not flags.
+        let mut rest: Vec<String> = Vec::new();
+
+        let mut i = 0;
+        while i < args.len() {
+            // Check whether the argument selects an output format, and how many arguments it uses.
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                opts.format = format;
+                i += consumed;
+                continue;
+            }
+
+            match args[i].as_str() {
+                "--exit-code" => opts.exit_code = true,
+                "--apply-renames" => opts.apply_renames = true,
+                _ => rest.push(args[i].clone()),
+            }
+
+            i += 1;
+        }
+
+        Ok((opts, rest))
+    }
+
     /// Formats a given path by converting it into a concatenated string representation. This
     /// ensures that the resulting path string uses forward slashes as separators and is suitable
     /// for comparing local and remote locations.
@@ -145,7 +291,7 @@ impl<'a> Diff<'a> {
     ///
     /// Returns a `Result` containing the formatted path string on success,
     /// or an error of type `NeocitiesErr` if normalization fails.
-    fn format_path(&self, path: &PathBuf) -> Result<String, NeocitiesErr> {
+    pub(crate) fn format_path(&self, path: &PathBuf) -> Result<String, NeocitiesErr> {
         // Initialize an empty string to store the normalized path.
         let mut formatted = String::new();
 
@@ -180,7 +326,7 @@ impl<'a> Diff<'a> {
     ///
     /// Returns a `Result` containing the hexadecimal representation of the calculated SHA-1 hash
     /// if successful, or an error of type `NeocitiesErr` if an issue occurs.
-    fn hash(&self, filepath: &PathBuf) -> Result<String, NeocitiesErr> {
+    pub(crate) fn hash(&self, filepath: &PathBuf) -> Result<String, NeocitiesErr> {
         // Read the contents of the file into a byte vector.
         let contents = fs::read(filepath)?;
 
@@ -252,11 +398,13 @@ impl<'a> Diff<'a> {
             on_local: None,
             on_remote: None,
             remark: String::new(),
+            status: None,
         })
     }
 
     /// Populates the provided map with information about local items at the specified path.
-    /// Recursively scans subdirectories of the path and adds items therein to the map.
+    /// Recursively scans subdirectories of the path and adds items therein to the map, leaving
+    /// out the paths listed in the `.neocitiesignore` of the current directory.
     ///
     /// # Arguments
     ///
@@ -268,10 +416,22 @@ impl<'a> Diff<'a> {
     /// # Returns
     ///
     /// Returns a `Result` indicating success or an error of type `NeocitiesErr`.
-    fn local_items(
+    pub(crate) fn local_items(
         &self,
         map: &mut HashMap<String, Item>,
         target_path: PathBuf,
+    ) -> Result<(), NeocitiesErr> {
+        let ignore = Ignore::load(Path::new("."))?;
+        self.local_items_except(map, target_path, &ignore)
+    }
+
+    /// Populates the provided map like `local_items`, leaving out the paths that the given
+    /// ignore rules match.
+    pub(crate) fn local_items_except(
+        &self,
+        map: &mut HashMap<String, Item>,
+        target_path: PathBuf,
+        ignore: &Ignore,
     ) -> Result<(), NeocitiesErr> {
         // Use a formatted version of the target path as a key in the map.
         let key = self.format_path(&target_path)?;
@@ -288,6 +448,13 @@ impl<'a> Diff<'a> {
             for entry in read_dir(&target_path)? {
                 let entry = entry?;
 
+                // Skip the directory where this application keeps its local state, such as the
+                // trash, and the paths the project ignores, since they are not part of the
+                // website.
+                if state::is_state_dir(&entry.path()) || ignore.is_ignored(&entry.path()) {
+                    continue;
+                }
+
                 // Create key from formatted version of the entry path.
                 let key = self.format_path(&entry.path())?;
 
@@ -299,7 +466,7 @@ impl<'a> Diff<'a> {
 
                 // If the entry is a subdirectory, recursively scan it.
                 if entry.path().is_dir() {
-                    self.local_items(map, entry.path())?;
+                    self.local_items_except(map, entry.path(), ignore)?;
                 }
             }
         }
@@ -307,7 +474,8 @@ impl<'a> Diff<'a> {
         Ok(())
     }
 
-    /// Populates a HashMap with remote items that match a specified target path filter.
+    /// Populates a HashMap with remote items that match a specified target path filter, leaving
+    /// out the paths listed in the `.neocitiesignore` of the current directory.
     ///
     /// # Arguments
     ///
@@ -319,19 +487,39 @@ impl<'a> Diff<'a> {
     /// # Returns
     ///
     /// Returns a `Result` indicating success (`Ok`) or an error of type `NeocitiesErr`.
-    fn remote_items(
+    pub(crate) fn remote_items(
         &self,
         map: &mut HashMap<String, Item>,
         target_path: PathBuf,
         remote_list: ListResponse,
     ) -> Result<(), NeocitiesErr> {
+        let ignore = Ignore::load(Path::new("."))?;
+        self.remote_items_except(map, target_path, remote_list, &ignore)
+    }
+
+    /// Populates the provided map like `remote_items`, leaving out the paths that the given
+    /// ignore rules match, so that a remote copy of an ignored file is not reported as missing
+    /// locally.
+    pub(crate) fn remote_items_except(
+        &self,
+        map: &mut HashMap<String, Item>,
+        target_path: PathBuf,
+        remote_list: ListResponse,
+        ignore: &Ignore,
+    ) -> Result<(), NeocitiesErr> {
+        // Format the target path using a utility method, handling formatting errors if any. An
+        // empty target is the root of the site.
+        let target = self.format_path(&target_path)?;
+        let prefix = format!("{target}/");
+
         // Iterate over each file in the remote list.
         for file in remote_list.files.iter() {
-            // Format the target path using a utility method, handling formatting errors if any.
-            let target = self.format_path(&target_path)?;
+            // Check if the file is the target path itself or lies beneath it.
+            let beneath =
+                target.is_empty() || file.path == target || file.path.starts_with(&prefix);
 
-            // Check if the file's path contains the target path, indicating a match with the filter.
-            if file.path.contains(&target) {
+            // Leave out the paths the project ignores, which are never uploaded.
+            if beneath && !ignore.is_ignored_as(Path::new(&file.path), file.is_directory) {
                 // Create a new Item struct and insert remote item information into the provided HashMap.
                 map.insert(
                     // Use the file path as the key.
@@ -345,6 +533,8 @@ impl<'a> Diff<'a> {
                         on_local: None,
                         // Initialize the remark field as an empty string.
                         remark: String::new(),
+                        // The item has not been compared with its local counterpart yet.
+                        status: None,
                     },
                 );
             }
@@ -431,7 +621,7 @@ impl<'a> Diff<'a> {
     /// to determine the differences between them. It identifies files that exist remotely but not
     /// locally, files that exist locally but not remotely, and files that are shared between both
     /// locations. For shared files, it compares their SHA-1 hash values to detect modifications.
-    fn diff(
+    pub(crate) fn diff(
         &self,
         mut local_map: HashMap<String, Item>,
         mut remote_map: HashMap<String, Item>,
@@ -445,6 +635,8 @@ impl<'a> Diff<'a> {
             if let Some(mut item) = remote_map.remove(&key) {
                 // Mark the item as missing locally and add it to the diff_list.
                 item.remark = String::from("\x1b[;93m(missing) local not found\x1b[;0m");
+                item.on_local = Some(false);
+                item.status = Some(Status::Deleted);
                 diff_list.push(item);
             }
         }
@@ -455,6 +647,8 @@ impl<'a> Diff<'a> {
             if let Some(mut item) = local_map.remove(&key) {
                 // Mark the item as missing remotely and add it to the diff_list.
                 item.remark = String::from("\x1b[;93m(missing) remote not found\x1b[;0m");
+                item.on_remote = Some(false);
+                item.status = Some(Status::Added);
                 diff_list.push(item);
             }
         }
@@ -510,6 +704,15 @@ impl<'a> Diff<'a> {
                     );
                 }
 
+                // Treat the difference as a conflict unless the local version is the newer one.
+                let status = if local_date > remote_date {
+                    Status::Modified
+                } else {
+                    Status::Conflict
+                };
+                local_item.status = Some(status);
+                remote_item.status = Some(status);
+
                 // Add both local and remote items with differences to the diff_list.
                 diff_list.push(local_item);
                 diff_list.push(remote_item);
@@ -518,6 +721,343 @@ impl<'a> Diff<'a> {
 
         Ok(diff_list)
     }
+
+    /// Merges the items returned by `diff` into one `Change` per path, sorted by path.
+    ///
+    /// Modified and conflicting paths are represented by both a local and a remote item; the
+    /// remote one is recognized by having been marked as present locally during the comparison.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:  A reference to the `Diff` instance invoking the method.
+    /// - `items`: A reference to the items returned by `diff`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `Change`s, one per differing path.
+    pub(crate) fn changes(&self, items: &[Item]) -> Vec<Change> {
+        // Use a BTreeMap so the resulting changes are ordered by path.
+        let mut changes: BTreeMap<String, Change> = BTreeMap::new();
+
+        for item in items.iter() {
+            // Skip items that were not classified during the comparison.
+            let status = match item.status {
+                Some(s) => s,
+                None => continue,
+            };
+
+            // Determine whether the item describes the remote side of the path.
+            let is_remote = match status {
+                Status::Deleted => true,
+                Status::Added => false,
+                Status::Modified | Status::Conflict | Status::Renamed => {
+                    item.on_local == Some(true)
+                }
+            };
+
+            // Find or create the change for the item's path.
+            let change = changes
+                .entry(item.file.path.clone())
+                .or_insert_with(|| Change {
+                    status,
+                    path: item.file.path.clone(),
+                    from: None,
+                    is_directory: item.file.is_directory,
+                    local_sha1: None,
+                    local_size: None,
+                    remote_sha1: None,
+                });
+
+            // Record the item's hash on the appropriate side.
+            if is_remote {
+                change.remote_sha1 = item.file.sha1_hash.clone();
+            } else {
+                change.local_sha1 = item.file.sha1_hash.clone();
+                change.local_size = item.file.size;
+            }
+        }
+
+        changes.into_values().collect()
+    }
+
+    /// Compares a local path with its corresponding remote path and returns one `Change` per
+    /// differing path, sorted by path. This is the comparison used by the `diff` command, made
+    /// available to other commands that act upon the differences.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:        A reference to the `Diff` instance invoking the method.
+    /// - `local`:       The local path to be compared.
+    /// - `remote_list`: The complete list of remote files, as returned by `NcList::fetch(None)`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the changes, or an error of type `NeocitiesErr`.
+    pub(crate) fn compare(
+        &self,
+        local: PathBuf,
+        remote_list: ListResponse,
+    ) -> Result<Vec<Change>, NeocitiesErr> {
+        // Gather the local and remote items for the path.
+        let mut local_map: HashMap<String, Item> = HashMap::new();
+        self.local_items(&mut local_map, local.clone())?;
+
+        let mut remote_map: HashMap<String, Item> = HashMap::new();
+        self.remote_items(&mut remote_map, local, remote_list)?;
+
+        // Compare the items and merge them into one change per path.
+        let items = self.diff(local_map, remote_map)?;
+
+        Ok(self.changes(&items))
+    }
+
+    /// Detects renamed or moved files among the given changes.
+    ///
+    /// A file that exists on the remote but not locally (`Deleted`) and a file that exists locally
+    /// but not on the remote (`Added`) are considered to be the same file when their SHA-1 hashes
+    /// match. Each such pair is replaced by a single `Renamed` change. When several files share the
+    /// same content, they are paired in path order. Directories are never paired.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:    A reference to the `Diff` instance invoking the method.
+    /// - `changes`: The changes returned by `changes`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the changes, sorted by path, with matching additions and deletions replaced by
+    /// renames.
+    fn detect_renames(&self, changes: Vec<Change>) -> Vec<Change> {
+        // Group the paths of added files by their hash, in path order.
+        let mut added: HashMap<String, Vec<String>> = HashMap::new();
+        for change in changes.iter() {
+            if change.status == Status::Added && !change.is_directory {
+                if let Some(sha) = &change.local_sha1 {
+                    added
+                        .entry(sha.to_owned())
+                        .or_default()
+                        .push(change.path.clone());
+                }
+            }
+        }
+
+        // Pair each deleted file with the first unclaimed added file that has the same hash.
+        let mut renames: HashMap<String, String> = HashMap::new();
+        for change in changes.iter() {
+            if change.status != Status::Deleted || change.is_directory {
+                continue;
+            }
+
+            let candidates = match change.remote_sha1.as_ref().and_then(|h| added.get_mut(h)) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            if !candidates.is_empty() {
+                // The candidates are in path order, so claim the first one.
+                renames.insert(candidates.remove(0), change.path.clone());
+            }
+        }
+
+        // Collect the old paths of the renamed files, so their deletions can be dropped.
+        let sources: HashSet<String> = renames.values().cloned().collect();
+
+        let mut result: Vec<Change> = Vec::new();
+        for mut change in changes.into_iter() {
+            match change.status {
+                Status::Deleted if sources.contains(&change.path) => continue,
+                Status::Added => {
+                    if let Some(from) = renames.remove(&change.path) {
+                        // Record the move on the change for the new path.
+                        change.status = Status::Renamed;
+                        change.remote_sha1 = change.local_sha1.clone();
+                        change.from = Some(from);
+                    }
+                }
+                _ => {}
+            }
+            result.push(change);
+        }
+
+        result
+    }
+
+    /// Renames files on the remote according to the `Renamed` changes, using the Neocities rename
+    /// endpoint instead of uploading the files again. A rename that fails does not stop the
+    /// others.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:    A reference to the `Diff` instance invoking the method.
+    /// - `changes`: A reference to the changes returned by `detect_renames`.
+    /// - `rename`:  The function that renames a file on the remote, such as `NcRename::fetch`.
+    /// - `writer`:  A mutable reference to a writer implementing the `std::io::Write` trait, to
+    ///              which the server's response for each rename is written.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the number of files that were renamed, or
+    /// `NeocitiesErr::MoveFailed` after all renames were tried if any of them failed.
+    fn apply_renames(
+        &self,
+        changes: &[Change],
+        rename: impl Fn(&str, &str) -> Result<RenameResponse, NeocitiesErr>,
+        mut writer: impl std::io::Write,
+    ) -> Result<usize, NeocitiesErr> {
+        let mut renamed = 0;
+        let mut failed: Vec<&str> = Vec::new();
+
+        for change in changes.iter().filter(|c| c.status == Status::Renamed) {
+            let from = match &change.from {
+                Some(f) => f,
+                None => continue,
+            };
+
+            // Ask the server to move the file, then report its answer.
+            let output = match rename(from, &change.path) {
+                Ok(res) => {
+                    match res.result.as_str() {
+                        "success" => renamed += 1,
+                        _ => failed.push(from),
+                    }
+                    format!("\x1b[93mStatus\x1b[0m: {} - {}\n", res.result, res.message)
+                }
+                Err(e) => {
+                    failed.push(from);
+                    format!("\x1b[93mStatus\x1b[0m: error - {from}: {e}\n")
+                }
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        // Report the files that were not renamed once every rename has been tried.
+        if !failed.is_empty() {
+            let output = format!(
+                "\x1b[91mNot renamed\x1b[0m ({} of {}): {}\n",
+                failed.len(),
+                failed.len() + renamed,
+                failed.join(", ")
+            );
+            self.write(output.as_str(), &mut writer)?;
+            return Err(NeocitiesErr::MoveFailed);
+        }
+
+        Ok(renamed)
+    }
+
+    /// Writes the differences in a human readable format.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:    A reference to the `Diff` instance invoking the method.
+    /// - `items`:   A reference to the items returned by `diff`.
+    /// - `changes`: A reference to the changes returned by `detect_renames`, used to report renamed
+    ///              files as moves instead of as a deletion and an addition.
+    /// - `writer`:  A mutable reference to a writer implementing the `std::io::Write` trait.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating success or an error of type `NeocitiesErr`.
+    fn write_text(
+        &self,
+        items: &[Item],
+        changes: &[Change],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        // Check if there are no differences.
+        if items.is_empty() {
+            // If no differences, write a message indicating synchronization and return.
+            self.write("Local and remote version are in sync\n", &mut writer)?;
+            return Ok(());
+        }
+
+        // Collect both the old and new paths of renamed files.
+        let mut moved: HashSet<&str> = HashSet::new();
+        for change in changes.iter().filter(|c| c.status == Status::Renamed) {
+            moved.insert(change.path.as_str());
+            if let Some(from) = &change.from {
+                moved.insert(from.as_str());
+            }
+        }
+
+        // Iterate over each differing item, except renamed ones, and write their details.
+        for item in items
+            .iter()
+            .filter(|i| !moved.contains(i.file.path.as_str()))
+        {
+            let output = format!("\x1b[1;97m{}\x1b[0m <- {}\n", item.file.path, item.remark);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        // Report each renamed file as a single move.
+        for change in changes.iter().filter(|c| c.status == Status::Renamed) {
+            let output = format!(
+                "\x1b[1;97m{}\x1b[0m <- \x1b[;96m(moved) from {}\x1b[;0m\n",
+                change.path,
+                change.from.as_deref().unwrap_or_default()
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the differences in a stable, line-oriented format: one status code and path per
+    /// line, separated by a tab. Renamed files are written as `R`, the old path and the new path.
+    /// Nothing is written when the paths are in sync.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:    A reference to the `Diff` instance invoking the method.
+    /// - `changes`: A reference to the changes returned by `changes`.
+    /// - `writer`:  A mutable reference to a writer implementing the `std::io::Write` trait.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating success or an error of type `NeocitiesErr`.
+    fn write_porcelain(
+        &self,
+        changes: &[Change],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for change in changes {
+            // Renamed files list their old path before their new path.
+            let output = match &change.from {
+                Some(from) => format!("{}\t{}\t{}\n", change.status.code(), from, change.path),
+                None => format!("{}\t{}\n", change.status.code(), change.path),
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the differences as a JSON document.
+    ///
+    /// # Arguments
+    ///
+    /// - `self`:    A reference to the `Diff` instance invoking the method.
+    /// - `changes`: A reference to the changes returned by `changes`.
+    /// - `writer`:  A mutable reference to a writer implementing the `std::io::Write` trait.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` indicating success or an error of type `NeocitiesErr`.
+    fn write_json(
+        &self,
+        changes: &[Change],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let report = DiffReport {
+            in_sync: changes.is_empty(),
+            changes,
+        };
+
+        let output = format!("{}\n", serde_json::to_string_pretty(&report)?);
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
 }
 
 /// Implementation of the Executable trait for the Diff struct.
@@ -537,7 +1077,10 @@ impl<'a> Executable for Diff<'a> {
             return Ok(());
         }
 
-        // Parse the provided arguments to obtain a local path.
+        // Separate the option flags from the path argument.
+        let (opts, args) = self.parse_opts(args)?;
+
+        // Parse the remaining arguments to obtain a local path.
         let local = self.parse_args(args)?;
 
         // Create a HashMap to store local item information.
@@ -561,17 +1104,32 @@ impl<'a> Executable for Diff<'a> {
         // Get the differences between local and remote versions.
         let items = self.diff(local_map, remote_map)?;
 
-        // Check if there are no differences.
-        if items.len() < 1 {
-            // If no differences, write a message indicating synchronization and return.
-            self.write("Local and remote version are in sync\n", &stdout)?;
-            return Ok(());
+        // Merge the items into one change per path, and pair up renamed files.
+        let changes = self.detect_renames(self.changes(&items));
+
+        // Write the differences in the requested format.
+        match opts.format {
+            Format::Text => self.write_text(&items, &changes, &stdout)?,
+            Format::Porcelain => self.write_porcelain(&changes, &stdout)?,
+            Format::Json => self.write_json(&changes, &stdout)?,
         }
 
-        // Iterate over each differing item and format and write their details to stdout.
-        for item in items {
-            let output = format!("\x1b[1;97m{}\x1b[0m <- {}\n", item.file.path, item.remark);
-            self.write(output.as_str(), &stdout)?;
+        // Count the changes that remain after the output was written.
+        let mut remaining = changes.len();
+
+        // Carry out the renames if requested. Responses go to stderr for scripted formats so they
+        // don't corrupt the output.
+        if opts.apply_renames {
+            let renamed = match opts.format {
+                Format::Text => self.apply_renames(&changes, NcRename::fetch, &stdout)?,
+                _ => self.apply_renames(&changes, NcRename::fetch, std::io::stderr())?,
+            };
+            remaining -= renamed;
+        }
+
+        // Signal a difference through the exit code if requested.
+        if opts.exit_code && remaining > 0 {
+            return Err(NeocitiesErr::OutOfSync);
         }
 
         Ok(())
@@ -601,7 +1159,18 @@ impl<'a> Executable for Diff<'a> {
 
 // The DESCRIPTION constant contains a brief explanation of the purpose of the Diff command.
 const DESC: &'static str =
-    "Compare the state of a local path in your project with a corresponding path on your Neocities website.";
+    "Compare the state of a local path in your project with a corresponding path on your Neocities website.
+
+Use `--output porcelain` for one status code and path per line, where A means a local item is not
+on the remote, D means a remote item is not local, M means the local copy is newer, C means the
+remote copy is newer, and R means a file was renamed or moved (followed by its old and new paths).
+Use `--output json` for a JSON document, and `--exit-code` to exit with status 1 when the paths are
+out of sync; errors exit with status 2. Use `--apply-renames` to move renamed files on Neocities
+instead of uploading them again.";
+
+// The USAGE_ARGS constant lists the arguments accepted by the Diff command.
+const USAGE_ARGS: &'static str =
+    "[--output <text|porcelain|json>] [--exit-code] [--apply-renames] ./<path>";
 
 // The DESCRIPTION constant contains a brief explanation of the purpose of the Diff command.
 const DESC_SHORT: &'static str = "Compare a local and a remote path.";
@@ -614,7 +1183,7 @@ mod tests {
 
     use crate::{
         api::list::{File, ListResponse},
-        client::diff::{Item, DESC, DESC_SHORT, KEY},
+        client::diff::{Item, DESC, DESC_SHORT, KEY, USAGE_ARGS},
         error::NeocitiesErr,
     };
 
@@ -634,7 +1203,7 @@ mod tests {
         let expected_desc_short = DESC_SHORT;
 
         // The expected usage information with ANSI color formatting (usage).
-        let expected_usage = format!("\x1b[1;32m{KEY}\x1b[0m ./<path>");
+        let expected_usage = format!("\x1b[1;32m{KEY}\x1b[0m {USAGE_ARGS}");
 
         // Check that the actual values of the fields in the `diff` instance match the expected values.
 
@@ -918,6 +1487,12 @@ mod tests {
         // Check that the map contains the expected number of items (3 in this case).
         assert_eq!(map.len(), 3);
 
+        // Check that the paths matched by the ignore rules are left out.
+        let ignore = Ignore::parse(&target_dir, "*.txt\n")?;
+        let mut map: HashMap<String, Item> = HashMap::new();
+        diff.local_items_except(&mut map, target_dir.clone(), &ignore)?;
+        assert_eq!(map.len(), 2);
+
         Ok(())
     }
 
@@ -978,6 +1553,58 @@ mod tests {
         assert_eq!(remote_item.on_remote, Some(true));
         assert_eq!(remote_item.on_local, None);
 
+        // Check that remote paths which merely contain the target path are left out.
+        let mut mock_remote_map: HashMap<String, Item> = HashMap::new();
+        let mock_list_response = ListResponse {
+            result: String::from("mock"),
+            files: [
+                "site",
+                "site/index.html",
+                "website.html",
+                "blog/site-map.xml",
+            ]
+            .iter()
+            .map(|p| File {
+                path: String::from(*p),
+                is_directory: *p == "site",
+                size: None,
+                updated_at: String::from("2023-08-01T12:34:56Z"),
+                sha1_hash: None,
+            })
+            .collect(),
+        };
+        diff.remote_items(
+            &mut mock_remote_map,
+            PathBuf::from("./site/"),
+            mock_list_response.clone(),
+        )?;
+        let mut keys: Vec<&String> = mock_remote_map.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["site", "site/index.html"]);
+
+        // Check that an empty target path selects the whole site.
+        let mut mock_remote_map: HashMap<String, Item> = HashMap::new();
+        diff.remote_items(
+            &mut mock_remote_map,
+            PathBuf::from("."),
+            mock_list_response.clone(),
+        )?;
+        assert_eq!(mock_remote_map.len(), 4);
+
+        // Check that the remote copies of ignored paths are left out, so that they are not
+        // reported as deleted.
+        let ignore = Ignore::parse(Path::new("."), "*.xml\nsite/\n")?;
+        let mut mock_remote_map: HashMap<String, Item> = HashMap::new();
+        diff.remote_items_except(
+            &mut mock_remote_map,
+            PathBuf::from("."),
+            mock_list_response,
+            &ignore,
+        )?;
+        let mut keys: Vec<&String> = mock_remote_map.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["website.html"]);
+
         Ok(())
     }
 
@@ -1033,6 +1660,7 @@ mod tests {
                 on_remote: None,
                 on_local: None,
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1049,6 +1677,7 @@ mod tests {
                 on_local: None,
                 on_remote: None,
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1069,6 +1698,7 @@ mod tests {
                 on_local: Some(false),
                 on_remote: Some(true),
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1095,6 +1725,7 @@ mod tests {
                 on_remote: None,
                 on_local: None,
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1129,6 +1760,7 @@ mod tests {
                 on_remote: None,
                 on_local: None,
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1149,6 +1781,7 @@ mod tests {
                 on_local: Some(false),
                 on_remote: Some(true),
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1194,6 +1827,7 @@ mod tests {
                 on_remote: None,
                 on_local: Some(true),
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1210,6 +1844,7 @@ mod tests {
                 on_remote: Some(true),
                 on_local: None,
                 remark: String::new(),
+                status: None,
             },
         );
 
@@ -1235,4 +1870,268 @@ mod tests {
             .iter()
             .any(|item| item.file.path == "path/to/remote_item1"));
     }
+
+    #[test]
+    fn test_parse_opts() -> Result<(), NeocitiesErr> {
+        // Create a test `Diff` instance for testing.
+        let diff = Diff::new();
+
+        // Parse a mix of flags and a path.
+        let args: Vec<String> = vec!["--output", "porcelain", "site", "--exit-code"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (opts, rest) = diff.parse_opts(args)?;
+
+        // Check that the flags were recognized and the path was kept.
+        assert_eq!(opts.format, Format::Porcelain);
+        assert_eq!(opts.exit_code, true);
+        assert_eq!(rest, vec!["site".to_string()]);
+
+        // Check that the defaults apply when no flags are given.
+        let (opts, rest) = diff.parse_opts(vec!["site".to_string()])?;
+        assert_eq!(opts.format, Format::Text);
+        assert_eq!(opts.exit_code, false);
+        assert_eq!(rest.len(), 1);
+
+        // Check that an unknown output format is rejected.
+        let args = vec!["--output".to_string(), "xml".to_string()];
+        assert!(diff.parse_opts(args).is_err());
+
+        Ok(())
+    }
+
+    /// Builds an item for a file with the given path, hash and timestamp.
+    fn mock_item(path: &str, sha1_hash: &str, updated_at: &str) -> Item {
+        Item {
+            file: File {
+                path: path.to_string(),
+                is_directory: false,
+                size: Some(1),
+                updated_at: updated_at.to_string(),
+                sha1_hash: Some(sha1_hash.to_string()),
+            },
+            on_remote: None,
+            on_local: None,
+            remark: String::new(),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_changes() -> Result<(), NeocitiesErr> {
+        // Create a test `Diff` instance for testing.
+        let diff = Diff::new();
+
+        let older = "Sat, 13 Feb 2016 03:04:00 -0000";
+        let newer = "Sun, 14 Feb 2016 03:04:00 -0000";
+
+        // Populate the local map: one new file, one modified file, one file edited remotely.
+        let mut local_map: HashMap<String, Item> = HashMap::new();
+        local_map.insert(
+            "site/new.html".to_string(),
+            mock_item("site/new.html", "a", older),
+        );
+        local_map.insert(
+            "site/index.html".to_string(),
+            mock_item("site/index.html", "b", newer),
+        );
+        local_map.insert(
+            "site/about.html".to_string(),
+            mock_item("site/about.html", "c", older),
+        );
+
+        // Populate the remote map: one removed file and the remote copies of the shared files.
+        let mut remote_map: HashMap<String, Item> = HashMap::new();
+        remote_map.insert(
+            "site/old.html".to_string(),
+            mock_item("site/old.html", "d", older),
+        );
+        remote_map.insert(
+            "site/index.html".to_string(),
+            mock_item("site/index.html", "e", older),
+        );
+        remote_map.insert(
+            "site/about.html".to_string(),
+            mock_item("site/about.html", "f", newer),
+        );
+
+        // Compare the maps and merge the items into changes.
+        let items = diff.diff(local_map, remote_map)?;
+        let changes = diff.changes(&items);
+
+        // Check that there is exactly one change per path, sorted by path.
+        let summary: Vec<(char, &str)> = changes
+            .iter()
+            .map(|c| (c.status.code(), c.path.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ('C', "site/about.html"),
+                ('M', "site/index.html"),
+                ('A', "site/new.html"),
+                ('D', "site/old.html"),
+            ]
+        );
+
+        // Check that the hashes were recorded on the correct sides.
+        assert_eq!(changes[1].local_sha1, Some("b".to_string()));
+        assert_eq!(changes[1].remote_sha1, Some("e".to_string()));
+        assert_eq!(changes[2].remote_sha1, None);
+        assert_eq!(changes[3].local_sha1, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_porcelain_and_json() -> Result<(), NeocitiesErr> {
+        // Create a test `Diff` instance for testing.
+        let diff = Diff::new();
+
+        let changes = vec![Change {
+            status: Status::Added,
+            path: String::from("site/new.html"),
+            from: None,
+            is_directory: false,
+            local_sha1: Some(String::from("a")),
+            local_size: Some(1),
+            remote_sha1: None,
+        }];
+
+        // Check the porcelain format.
+        let mut writer = Cursor::new(Vec::new());
+        diff.write_porcelain(&changes, &mut writer)?;
+        assert_eq!(
+            String::from_utf8(writer.into_inner())?,
+            "A\tsite/new.html\n"
+        );
+
+        // Check that porcelain output is empty when in sync.
+        let mut writer = Cursor::new(Vec::new());
+        diff.write_porcelain(&[], &mut writer)?;
+        assert_eq!(writer.into_inner().len(), 0);
+
+        // Check the JSON format.
+        let mut writer = Cursor::new(Vec::new());
+        diff.write_json(&changes, &mut writer)?;
+        let v: serde_json::Value = serde_json::from_slice(&writer.into_inner())?;
+        assert_eq!(v["in_sync"], false);
+        assert_eq!(v["changes"][0]["status"], "A");
+        assert_eq!(v["changes"][0]["path"], "site/new.html");
+        assert_eq!(v["changes"][0]["local_sha1"], "a");
+        assert_eq!(v["changes"][0]["remote_sha1"], serde_json::Value::Null);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_renames() -> Result<(), NeocitiesErr> {
+        // Create a test `Diff` instance for testing.
+        let diff = Diff::new();
+
+        let date = "Sat, 13 Feb 2016 03:04:00 -0000";
+
+        // A directory was renamed from img to images, and an unrelated file was added.
+        let mut local_map: HashMap<String, Item> = HashMap::new();
+        local_map.insert(
+            "images/a.png".to_string(),
+            mock_item("images/a.png", "a", date),
+        );
+        local_map.insert(
+            "images/b.png".to_string(),
+            mock_item("images/b.png", "b", date),
+        );
+        local_map.insert("new.html".to_string(), mock_item("new.html", "c", date));
+
+        let mut remote_map: HashMap<String, Item> = HashMap::new();
+        remote_map.insert("img/a.png".to_string(), mock_item("img/a.png", "a", date));
+        remote_map.insert("img/b.png".to_string(), mock_item("img/b.png", "b", date));
+        remote_map.insert("old.html".to_string(), mock_item("old.html", "d", date));
+
+        // Compare the maps, then pair up the renamed files.
+        let items = diff.diff(local_map, remote_map)?;
+        let changes = diff.detect_renames(diff.changes(&items));
+
+        // Check that each move is reported once, and that other changes are untouched.
+        let summary: Vec<(char, Option<&str>, &str)> = changes
+            .iter()
+            .map(|c| (c.status.code(), c.from.as_deref(), c.path.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ('R', Some("img/a.png"), "images/a.png"),
+                ('R', Some("img/b.png"), "images/b.png"),
+                ('A', None, "new.html"),
+                ('D', None, "old.html"),
+            ]
+        );
+
+        // Check that a failed rename does not stop the others, and is reported at the end.
+        let rename = |from: &str, to: &str| match from {
+            "img/a.png" => Err(NeocitiesErr::InvalidArgument),
+            _ => Ok(RenameResponse {
+                result: String::from("success"),
+                error_type: None,
+                message: format!("{from} renamed to {to}"),
+            }),
+        };
+        let mut writer = Cursor::new(Vec::new());
+        let result = diff.apply_renames(&changes, rename, &mut writer);
+        assert!(matches!(result, Err(NeocitiesErr::MoveFailed)));
+        let text = String::from_utf8(writer.into_inner())?;
+        assert!(text.contains("error - img/a.png: invalid argument"));
+        assert!(text.contains("success - img/b.png renamed to images/b.png"));
+        assert!(text.contains("(1 of 2): img/a.png"));
+
+        // Check the porcelain format of a rename.
+        let mut writer = Cursor::new(Vec::new());
+        diff.write_porcelain(&changes[..1], &mut writer)?;
+        assert_eq!(
+            String::from_utf8(writer.into_inner())?,
+            "R\timg/a.png\timages/a.png\n"
+        );
+
+        // Check that the text format reports the move instead of a deletion and an addition.
+        let mut writer = Cursor::new(Vec::new());
+        diff.write_text(&items, &changes, &mut writer)?;
+        let text = String::from_utf8(writer.into_inner())?;
+        assert!(text.contains("(moved) from img/a.png"));
+        assert!(!text.contains("img/a.png\x1b[0m"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_renames_duplicate_content() {
+        // Create a test `Diff` instance for testing.
+        let diff = Diff::new();
+
+        let change =
+            |status: Status, path: &str, local: Option<&str>, remote: Option<&str>| Change {
+                status,
+                path: path.to_string(),
+                from: None,
+                is_directory: false,
+                local_sha1: local.map(|s| s.to_string()),
+                local_size: local.map(|_| 1),
+                remote_sha1: remote.map(|s| s.to_string()),
+            };
+
+        // Two new files share the content of a single removed file.
+        let changes = vec![
+            change(Status::Added, "b/x.txt", Some("same"), None),
+            change(Status::Added, "b/y.txt", Some("same"), None),
+            change(Status::Deleted, "a/x.txt", None, Some("same")),
+        ];
+
+        let result = diff.detect_renames(changes);
+
+        // Check that only one of the additions is treated as a move.
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].status, Status::Renamed);
+        assert_eq!(result[0].from.as_deref(), Some("a/x.txt"));
+        assert_eq!(result[1].status, Status::Added);
+    }
 }
@@ -0,0 +1,319 @@
+use super::{
+    command::Executable,
+    output::{format_bytes, Format},
+    tree::{Node, RemoteTree},
+    usage::DirUsage,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::NcList,
+    },
+    error::NeocitiesErr,
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "du";
+
+/// Shows the space used by each directory of a Neocities user's website
+pub struct Du {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The options given to `du`
+#[derive(Debug, PartialEq)]
+struct DuOpts {
+    /// The directory to report on, or the whole website
+    path: Option<String>,
+    /// How many levels of directories below the path are shown; unlimited if `None`
+    max_depth: Option<usize>,
+    human_readable: bool,
+    /// Largest first instead of `du`'s order, where a directory follows its subdirectories
+    sort: bool,
+    format: Format,
+}
+
+impl Du {
+    /// A constructor that returns an instance of `Du`.
+    pub fn new() -> Du {
+        Du {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m [<path>] [-L|--max-depth <n>] [--human-readable] [--sort] [--output <text|porcelain|json>]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<DuOpts, NeocitiesErr> {
+        let mut opts = DuOpts {
+            path: None,
+            max_depth: None,
+            human_readable: false,
+            sort: false,
+            format: Format::Text,
+        };
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                opts.format = format;
+                i += consumed;
+                continue;
+            }
+
+            match args[i].as_str() {
+                "--human-readable" => opts.human_readable = true,
+                "--sort" => opts.sort = true,
+                "-L" | "--max-depth" => {
+                    opts.max_depth = match args.get(i + 1).map(|n| n.parse::<usize>()) {
+                        Some(Ok(n)) => Some(n),
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                a if !a.starts_with('-') && opts.path.is_none() => opts.path = Some(a.to_string()),
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 1;
+        }
+
+        Ok(opts)
+    }
+
+    /// Returns the space used under the node and each directory beneath it, down to `max_depth`
+    /// levels, in the order `du` prints them: each directory after its subdirectories.
+    fn collect(&self, node: &Node, max_depth: Option<usize>) -> Vec<DirUsage> {
+        let mut dirs: Vec<DirUsage> = Vec::new();
+
+        if max_depth != Some(0) {
+            for child in node.children.values().filter(|c| c.is_directory()) {
+                dirs.extend(self.collect(child, max_depth.map(|d| d - 1)));
+            }
+        }
+
+        dirs.push(DirUsage {
+            path: node.path.clone(),
+            size: node.size,
+            files: node.files().len(),
+        });
+        dirs
+    }
+
+    fn write_text(
+        &self,
+        dirs: &[DirUsage],
+        human_readable: bool,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for dir in dirs.iter() {
+            let size = match human_readable {
+                true => format_bytes(dir.size),
+                false => dir.size.to_string(),
+            };
+            let path = match dir.path.is_empty() {
+                true => String::from("/"),
+                false => format!("{}/", dir.path),
+            };
+            let output = format!("{size:>10}  \x1b[1;36m{path}\x1b[0m\n");
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one line per directory: its size in bytes, its number of files and its path,
+    /// separated by tabs. The root of the website is `/`.
+    fn write_porcelain(
+        &self,
+        dirs: &[DirUsage],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for dir in dirs.iter() {
+            let output = format!("{}\t{}\t/{}\n", dir.size, dir.files, dir.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Du {
+    fn default() -> Du {
+        Du::new()
+    }
+}
+
+impl Executable for Du {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let opts = self.parse_opts(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let tree = RemoteTree::from_list(&NcList::fetch(None)?);
+        let node = match tree.get(opts.path.as_deref().unwrap_or_default()) {
+            Some(n) => n,
+            None => return Err(NeocitiesErr::MissingFile),
+        };
+
+        let mut dirs = self.collect(node, opts.max_depth);
+        if opts.sort {
+            dirs.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+        }
+
+        match opts.format {
+            Format::Json => {
+                let output = format!("{}\n", serde_json::to_string_pretty(&dirs)?);
+                self.write(output.as_str(), &mut stdout)?;
+            }
+            Format::Porcelain => self.write_porcelain(&dirs, &mut stdout)?,
+            Format::Text => self.write_text(&dirs, opts.human_readable, &mut stdout)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Show the total size of the files under each directory of your Neocities \
+website, or of one directory of it, like `du` does. Sizes are in bytes, or in KiB, MiB and GiB \
+with `--human-readable`. `-L <n>` or `--max-depth <n>` shows only directories up to n levels \
+down (0 shows the total alone), and `--sort` lists the largest directories first. Use `--output \
+json` or `--output porcelain` for scripts.";
+
+const DESC_SHORT: &str = "Show disk usage per directory";
+
+#[cfg(test)]
+mod tests {
+    use super::{Du, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{command::Executable, output::Format, tree::RemoteTree},
+        error::NeocitiesErr,
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let d = Du::new();
+        assert_eq!(d.get_usage().contains(KEY), true);
+        assert_eq!(d.get_short_desc(), DESC_SHORT);
+        assert_eq!(d.get_long_desc(), DESC);
+    }
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    fn tree() -> RemoteTree {
+        let file = |path: &str, size: i64| File {
+            path: path.to_string(),
+            is_directory: false,
+            size: Some(size),
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: None,
+        };
+
+        RemoteTree::from_list(&ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("index.html", 100),
+                file("css/main.css", 2_000),
+                file("images/a.png", 3_000_000),
+                file("images/icons/b.png", 1_000),
+            ],
+        })
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let d = Du::new();
+
+        let opts = d.parse_opts(to_args("images -L 1 --human-readable --sort --json"))?;
+        assert_eq!(opts.path.as_deref(), Some("images"));
+        assert_eq!(opts.max_depth, Some(1));
+        assert_eq!(opts.human_readable, true);
+        assert_eq!(opts.sort, true);
+        assert_eq!(opts.format, Format::Json);
+
+        let opts = d.parse_opts(vec![])?;
+        assert_eq!((opts.path, opts.max_depth), (None, None));
+
+        assert_eq!(d.parse_opts(to_args("--max-depth")).is_err(), true);
+        assert_eq!(d.parse_opts(to_args("--max-depth -1")).is_err(), true);
+        assert_eq!(d.parse_opts(to_args("one two")).is_err(), true);
+        // `-h` is left for help, as in the other commands
+        assert_eq!(d.parse_opts(to_args("-h")).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collect_method() {
+        let d = Du::new();
+        let tree = tree();
+
+        let dirs = d.collect(tree.root(), None);
+        let paths: Vec<&str> = dirs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["css", "images/icons", "images", ""]);
+        assert_eq!(dirs[2].size, 3_001_000);
+        assert_eq!(dirs[2].files, 2);
+        assert_eq!(dirs[3].size, 3_003_100);
+
+        let dirs = d.collect(tree.root(), Some(1));
+        let paths: Vec<&str> = dirs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["css", "images", ""]);
+
+        let dirs = d.collect(tree.get("images").unwrap(), Some(0));
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs[0].path, "images");
+    }
+
+    #[test]
+    fn write_methods() -> Result<(), NeocitiesErr> {
+        let d = Du::new();
+        let tree = tree();
+        let dirs = d.collect(tree.root(), Some(1));
+
+        let mut output = Vec::new();
+        d.write_text(&dirs, true, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("2.9 MiB"), true);
+        assert_eq!(s.contains("images/"), true);
+
+        let mut output = Vec::new();
+        d.write_text(&dirs, false, &mut output)?;
+        assert_eq!(String::from_utf8(output)?.contains("3001000"), true);
+
+        let mut output = Vec::new();
+        d.write_porcelain(&dirs, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "2000\t1\t/css\n3001000\t2\t/images\n3003100\t4\t/\n"
+        );
+
+        Ok(())
+    }
+}
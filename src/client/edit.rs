@@ -0,0 +1,352 @@
+use super::{
+    command::Executable,
+    diff::Diff,
+    journal::{JournalEntry, JournalFile},
+    objects,
+    trash::{public_url, sitename},
+    tree::RemoteTree,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        http::get_file,
+        list::{ListResponse, NcList},
+        upload::NcUpload,
+    },
+    error::NeocitiesErr,
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tempfile::TempDir;
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "edit";
+
+/// The environment variable that names the editor to open
+pub const ENV_EDITOR: &str = "EDITOR";
+
+/// The editor opened when `EDITOR` is not set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Opens a file on a Neocities user's website in a local editor, and uploads it when saved
+pub struct Edit {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// Returns the editor to open, with any arguments it was given in the environment (e.g.
+/// `code --wait`).
+pub fn editor() -> Vec<String> {
+    let editor = env::var(ENV_EDITOR).unwrap_or_default();
+    match editor
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>()
+    {
+        e if e.is_empty() => vec![String::from(DEFAULT_EDITOR)],
+        e => e,
+    }
+}
+
+impl Edit {
+    /// A constructor that returns an instance of `Edit`.
+    pub fn new() -> Edit {
+        Edit {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m <path>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the SHA-1 hash the listing gives for the file at a path. It fails if the website
+    /// has no file there.
+    fn remote_hash(&self, list: &ListResponse, path: &str) -> Result<Option<String>, NeocitiesErr> {
+        let tree = RemoteTree::from_list(list);
+        match tree.get(path) {
+            Some(n) if n.is_directory() => Err(NeocitiesErr::InvalidPath),
+            Some(n) => Ok(n.file.as_ref().and_then(|f| f.sha1_hash.clone())),
+            None => Err(NeocitiesErr::MissingFile),
+        }
+    }
+
+    /// Keeps the temporary directory of edits that could not be uploaded, and tells the user
+    /// where to find them.
+    fn keep(
+        &self,
+        temp_dir: TempDir,
+        name: &str,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let kept = temp_dir.keep().join(name);
+        let output = format!(
+            "\x1b[93mWarning\x1b[0m: your edits are kept in {}\n",
+            kept.display()
+        );
+        self.write(output.as_str(), &mut writer)
+    }
+
+    /// Checks that a downloaded file has the SHA-1 hash the listing gives for it. The public
+    /// URL may serve a cached copy that is older than the file on the website, and editing it
+    /// would undo the newer changes.
+    fn check_download(&self, file: &Path, base: &Option<String>) -> Result<(), NeocitiesErr> {
+        let sha1_hash = Diff::new().hash(&file.to_path_buf())?;
+        match base {
+            Some(expected) if *expected != sha1_hash => Err(NeocitiesErr::EditConflict),
+            _ => Ok(()),
+        }
+    }
+
+    /// Opens a local file in the editor and waits for it to exit. Returns the new SHA-1 hash of
+    /// the file if the editor changed it, or `None` if it did not.
+    fn open_editor(&self, editor: &[String], file: &Path) -> Result<Option<String>, NeocitiesErr> {
+        let diff = Diff::new();
+        let before = diff.hash(&file.to_path_buf())?;
+
+        let (program, args) = match editor.split_first() {
+            Some(e) => e,
+            None => return Err(NeocitiesErr::EditorFailed),
+        };
+        let status = Command::new(program).args(args).arg(file).status()?;
+        if !status.success() {
+            return Err(NeocitiesErr::EditorFailed);
+        }
+
+        let after = diff.hash(&file.to_path_buf())?;
+        Ok((after != before).then_some(after))
+    }
+}
+
+impl Default for Edit {
+    fn default() -> Edit {
+        Edit::new()
+    }
+}
+
+impl Executable for Edit {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let path = match args.as_slice() {
+            [p] if !p.starts_with('-') => p.trim_matches('/').to_string(),
+            _ => return Err(NeocitiesErr::InvalidArgument),
+        };
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let base = self.remote_hash(&NcList::fetch(None)?, &path)?;
+
+        // the temporary file keeps the name of the remote file, so that the editor recognizes
+        // its type
+        let temp_dir = tempfile::tempdir()?;
+        let name = path.rsplit('/').next().unwrap_or_default();
+        let local: PathBuf = temp_dir.path().join(name);
+        let site = sitename()?;
+        fs::write(&local, get_file(public_url(&site, &path))?)?;
+        self.check_download(&local, &base)?;
+
+        let edited = match self.open_editor(&editor(), &local) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                self.write("No changes were made; nothing was uploaded\n", &mut stdout)?;
+                return Ok(());
+            }
+            Err(e) => {
+                self.keep(temp_dir, name, &mut stdout)?;
+                return Err(e);
+            }
+        };
+
+        // someone else may have changed the file in the meantime
+        let current = self.remote_hash(&NcList::fetch(None)?, &path)?;
+        if current != base {
+            self.keep(temp_dir, name, &mut stdout)?;
+            return Err(NeocitiesErr::EditConflict);
+        }
+
+        let local_str = match local.to_str() {
+            Some(s) => s.to_string(),
+            None => return Err(NeocitiesErr::InvalidPath),
+        };
+        let snapshot = JournalFile {
+            path: path.clone(),
+            size: Some(i64::try_from(fs::metadata(&local)?.len())?),
+            sha1_hash: Some(objects::store(&local)?),
+        };
+
+        let data = match NcUpload::fetch_as(vec![(local_str, path.clone())]) {
+            Ok(d) => d,
+            Err(e) => {
+                self.keep(temp_dir, name, &mut stdout)?;
+                return Err(e);
+            }
+        };
+        JournalEntry::new("upload", KEY, Some(site), vec![snapshot])
+            .respond(&data.result, data.error_type.clone(), &data.message)
+            .append()?;
+
+        let output = format!(
+            "\x1b[93mStatus\x1b[0m: {} - {} ({path}, SHA-1 {edited})\n",
+            data.result, data.message
+        );
+        self.write(output.as_str(), &mut stdout)?;
+
+        if data.result != "success" {
+            self.keep(temp_dir, name, &mut stdout)?;
+            return Err(NeocitiesErr::RequestFailed);
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Open a file on your Neocities website in your editor, for quick fixes. The \
+live file is downloaded to a temporary file and opened with $EDITOR (or vi). When the editor \
+exits cleanly and the file has changed, it is uploaded back to the same path. If the download \
+does not match the file listed on your site, or the file changed on your site while you were \
+editing, nothing is uploaded. Whenever your edits do not reach your \
+site, they are kept in the temporary file.";
+
+const DESC_SHORT: &str = "Edit a remote file in $EDITOR";
+
+#[cfg(test)]
+mod tests {
+    use super::{editor, Edit, DEFAULT_EDITOR, DESC, DESC_SHORT, ENV_EDITOR, KEY};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::command::Executable,
+        error::NeocitiesErr,
+    };
+    use serial_test::serial;
+    use std::{env, fs};
+
+    #[test]
+    fn usage_desc_methods() {
+        let e = Edit::new();
+        assert_eq!(e.get_usage().contains(KEY), true);
+        assert_eq!(e.get_short_desc(), DESC_SHORT);
+        assert_eq!(e.get_long_desc(), DESC);
+    }
+
+    #[test]
+    #[serial(env)]
+    fn editor_function() {
+        let prev = env::var(ENV_EDITOR);
+
+        env::set_var(ENV_EDITOR, "code --wait");
+        assert_eq!(editor(), vec!["code", "--wait"]);
+        env::set_var(ENV_EDITOR, " ");
+        assert_eq!(editor(), vec![DEFAULT_EDITOR]);
+        env::remove_var(ENV_EDITOR);
+        assert_eq!(editor(), vec![DEFAULT_EDITOR]);
+
+        match prev {
+            Ok(p) => env::set_var(ENV_EDITOR, p),
+            Err(_) => env::remove_var(ENV_EDITOR),
+        }
+    }
+
+    #[test]
+    fn remote_hash_method() -> Result<(), NeocitiesErr> {
+        let e = Edit::new();
+        let list = ListResponse {
+            result: String::from("success"),
+            files: vec![
+                File {
+                    path: String::from("css"),
+                    is_directory: true,
+                    size: None,
+                    updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+                    sha1_hash: None,
+                },
+                File {
+                    path: String::from("css/main.css"),
+                    is_directory: false,
+                    size: Some(10),
+                    updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+                    sha1_hash: Some(String::from("abc123")),
+                },
+            ],
+        };
+
+        assert_eq!(
+            e.remote_hash(&list, "/css/main.css")?,
+            Some(String::from("abc123"))
+        );
+        assert_eq!(e.remote_hash(&list, "css").is_err(), true);
+        assert_eq!(e.remote_hash(&list, "index.html").is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_download_method() -> Result<(), NeocitiesErr> {
+        let e = Edit::new();
+        let temp_dir = tempfile::tempdir()?;
+        let file = temp_dir.path().join("index.html");
+        fs::write(&file, "Hello, World!")?;
+
+        let sha1_hash = String::from("0a0a9f2a6772942557ab5355d76af442f8f65e01");
+        assert_eq!(e.check_download(&file, &Some(sha1_hash)).is_ok(), true);
+        assert_eq!(e.check_download(&file, &None).is_ok(), true);
+
+        // a stale copy from a cache
+        assert_eq!(
+            matches!(
+                e.check_download(&file, &Some(String::from("abc123"))),
+                Err(NeocitiesErr::EditConflict)
+            ),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_editor_method() -> Result<(), NeocitiesErr> {
+        let e = Edit::new();
+        let temp_dir = tempfile::tempdir()?;
+        let file = temp_dir.path().join("index.html");
+        fs::write(&file, "Hello, World!")?;
+
+        // an editor that saves without changing anything
+        assert_eq!(e.open_editor(&[String::from("true")], &file)?, None);
+
+        let sed: Vec<String> = vec!["sed", "-i", "s/Hello/Goodbye/"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(e.open_editor(&sed, &file)?.is_some(), true);
+        assert_eq!(fs::read_to_string(&file)?, "Goodbye, World!");
+
+        assert_eq!(
+            e.open_editor(&[String::from("false")], &file).is_err(),
+            true
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use crate::error::NeocitiesErr;
+use std::{env, fs::File, io::Read, path::Path};
+
+/// The environment variable that, when set to `1`, `true` or `yes`, applies the rules of the
+/// Neocities supporter plan
+pub const ENV_SUPPORTER: &str = "NEOCITIES_SUPPORTER";
+
+/// The file extensions that free Neocities accounts may upload, as listed at
+/// <https://neocities.org/site_files/allowed_types>. Keep it in step with that page.
+pub const ALLOWED_EXTENSIONS: [&str; 58] = [
+    "apng",
+    "asc",
+    "atom",
+    "avif",
+    "bin",
+    "css",
+    "csv",
+    "dae",
+    "eot",
+    "epub",
+    "geojson",
+    "gif",
+    "gltf",
+    "gpg",
+    "htm",
+    "html",
+    "ico",
+    "jpeg",
+    "jpg",
+    "js",
+    "json",
+    "jxl",
+    "key",
+    "kml",
+    "knowl",
+    "less",
+    "manifest",
+    "map",
+    "markdown",
+    "md",
+    "mf",
+    "mid",
+    "midi",
+    "mtl",
+    "obj",
+    "opml",
+    "osdx",
+    "otf",
+    "pdf",
+    "pgp",
+    "png",
+    "py",
+    "rdf",
+    "rss",
+    "sass",
+    "scss",
+    "svg",
+    "text",
+    "tsv",
+    "ttf",
+    "txt",
+    "webapp",
+    "webmanifest",
+    "webp",
+    "woff",
+    "woff2",
+    "xcf",
+    "xml",
+];
+
+/// The number of bytes read from the start of a file to sniff its contents
+const SNIFF_LEN: usize = 8192;
+
+/// Extensions of text formats, which should not contain NUL bytes
+const TEXT_EXTENSIONS: [&str; 24] = [
+    "asc", "atom", "css", "csv", "geojson", "htm", "html", "js", "json", "kml", "less", "manifest",
+    "map", "markdown", "md", "opml", "py", "rss", "sass", "scss", "svg", "text", "tsv", "txt",
+];
+
+/// Returns true if the rules of the supporter plan apply, as set in the environment.
+pub fn is_supporter() -> bool {
+    match env::var(ENV_SUPPORTER) {
+        Ok(v) => matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"),
+        Err(_) => false,
+    }
+}
+
+/// Checks a local file before it is uploaded, and returns the reason Neocities would reject it,
+/// if any. Free accounts may only upload the types in `ALLOWED_EXTENSIONS`, while supporters may
+/// upload any type. In either case, a file whose contents do not look like its extension is
+/// rejected.
+pub fn check(path: &str, supporter: bool) -> Result<Option<String>, NeocitiesErr> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    if !supporter {
+        match extension.as_deref() {
+            None => return Ok(Some(String::from("has no file extension"))),
+            Some(e) if !ALLOWED_EXTENSIONS.contains(&e) => {
+                return Ok(Some(format!(
+                    "`.{e}` files need a supporter plan (see `--supporter`)"
+                )))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut head: Vec<u8> = Vec::new();
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+
+    Ok(extension.and_then(|e| sniff(&e, &head)))
+}
+
+/// Compares the first bytes of a file with what its extension promises, and describes the
+/// mismatch, if any. Extensions it knows nothing about always pass.
+fn sniff(extension: &str, head: &[u8]) -> Option<String> {
+    // an empty file is a valid, if useless, file of any type
+    if head.is_empty() {
+        return None;
+    }
+
+    if TEXT_EXTENSIONS.contains(&extension) {
+        return match head.contains(&0) {
+            true => Some(format!(
+                "contains binary data, not the text a `.{extension}` file holds"
+            )),
+            false => None,
+        };
+    }
+
+    let looks_right = match extension {
+        "png" | "apng" => head.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "jpg" | "jpeg" => head.starts_with(&[0xff, 0xd8, 0xff]),
+        "gif" => head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a"),
+        "webp" => head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP"),
+        "avif" => head.get(4..12) == Some(b"ftypavif") || head.get(4..12) == Some(b"ftypavis"),
+        "ico" => head.starts_with(&[0, 0, 1, 0]),
+        "pdf" => head.starts_with(b"%PDF-"),
+        "woff" => head.starts_with(b"wOFF"),
+        "woff2" => head.starts_with(b"wOF2"),
+        "ttf" | "otf" => {
+            head.starts_with(&[0, 1, 0, 0])
+                || head.starts_with(b"OTTO")
+                || head.starts_with(b"true")
+        }
+        "mid" | "midi" => head.starts_with(b"MThd"),
+        _ => return None,
+    };
+
+    match looks_right {
+        true => None,
+        false => Some(format!("does not look like a `.{extension}` file")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, is_supporter, sniff, ENV_SUPPORTER};
+    use crate::error::NeocitiesErr;
+    use serial_test::serial;
+    use std::{env, fs};
+
+    #[test]
+    fn check_function() -> Result<(), NeocitiesErr> {
+        assert_eq!(check("tests/fixtures/foo.html", false)?, None);
+        assert_eq!(check("tests/fixtures/bar.js", false)?, None);
+
+        let temp_dir = tempfile::tempdir()?;
+        let exe = temp_dir.path().join("setup.exe");
+        fs::write(&exe, "MZ")?;
+        let exe = exe.to_str().unwrap();
+
+        assert_eq!(
+            check(exe, false)?,
+            Some(String::from(
+                "`.exe` files need a supporter plan (see `--supporter`)"
+            ))
+        );
+        assert_eq!(check(exe, true)?, None);
+
+        let bare = temp_dir.path().join("README");
+        fs::write(&bare, "hello")?;
+        assert_eq!(check(bare.to_str().unwrap(), false)?.is_some(), true);
+        assert_eq!(check(bare.to_str().unwrap(), true)?, None);
+
+        // sniffing applies to supporters too
+        let fake = temp_dir.path().join("photo.PNG");
+        fs::write(&fake, "<html></html>")?;
+        assert_eq!(
+            check(fake.to_str().unwrap(), true)?,
+            Some(String::from("does not look like a `.png` file"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sniff_function() {
+        assert_eq!(sniff("png", b"\x89PNG\r\n\x1a\n...."), None);
+        assert_eq!(sniff("jpg", &[0xff, 0xd8, 0xff, 0xe0]), None);
+        assert_eq!(sniff("gif", b"GIF89a..."), None);
+        assert_eq!(sniff("webp", b"RIFF\0\0\0\0WEBPVP8 "), None);
+        assert_eq!(sniff("jpg", b"GIF89a...").is_some(), true);
+        assert_eq!(sniff("html", b"<p>\0</p>").is_some(), true);
+        assert_eq!(sniff("html", b"<p>hi</p>"), None);
+        assert_eq!(sniff("png", b""), None);
+        assert_eq!(sniff("xcf", b"anything"), None);
+    }
+
+    #[test]
+    #[serial(env)]
+    fn is_supporter_function() {
+        let prev = env::var(ENV_SUPPORTER);
+
+        env::set_var(ENV_SUPPORTER, "true");
+        assert_eq!(is_supporter(), true);
+        env::set_var(ENV_SUPPORTER, "0");
+        assert_eq!(is_supporter(), false);
+        env::remove_var(ENV_SUPPORTER);
+        assert_eq!(is_supporter(), false);
+
+        match prev {
+            Ok(p) => env::set_var(ENV_SUPPORTER, p),
+            Err(_) => env::remove_var(ENV_SUPPORTER),
+        }
+    }
+}
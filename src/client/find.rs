@@ -0,0 +1,519 @@
+use super::{
+    command::Executable,
+    log::parse_date,
+    output::{format_bytes, parse_bytes, Format},
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::{File, NcList},
+    },
+    error::NeocitiesErr,
+};
+use chrono::{DateTime, Utc};
+use glob::Pattern;
+use std::cmp::Ordering;
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "find";
+
+/// Searches the files of a Neocities user's website by name, type, size and modification date.
+pub struct Find {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The field by which matching files are sorted
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    Name,
+    Size,
+    Date,
+}
+
+/// The conditions a file must meet to be found, and how the results are ordered
+#[derive(Debug)]
+struct FindQuery {
+    /// The directory to search, without slashes at either end, or the whole website
+    path: Option<String>,
+    /// A glob that the last component of the path must match
+    name: Option<Pattern>,
+    /// Only directories if true, only files if false
+    is_directory: Option<bool>,
+    /// The exact size a file must have, in bytes
+    size: Option<u64>,
+    /// The size a file must be larger than, in bytes
+    larger_than: Option<u64>,
+    /// The size a file must be smaller than, in bytes
+    smaller_than: Option<u64>,
+    /// A time the file must have been modified after
+    newer: Option<DateTime<Utc>>,
+    /// A time the file must have been modified before
+    older: Option<DateTime<Utc>>,
+    sort: SortKey,
+    descending: bool,
+    format: Format,
+}
+
+impl Find {
+    /// A constructor that returns an instance of `Find`.
+    pub fn new() -> Find {
+        Find {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m [<path>] [--name <glob>] [--type <f|d>] [--size <[+|-]size>] [--newer <date>] [--older <date>] [--sort <name|size|date>] [--desc] [--output <text|porcelain|json>]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<FindQuery, NeocitiesErr> {
+        let mut query = FindQuery {
+            path: None,
+            name: None,
+            is_directory: None,
+            size: None,
+            larger_than: None,
+            smaller_than: None,
+            newer: None,
+            older: None,
+            sort: SortKey::Name,
+            descending: false,
+            format: Format::Text,
+        };
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                query.format = format;
+                i += consumed;
+                continue;
+            }
+
+            let flag = args[i].as_str();
+            if flag == "--desc" || flag == "-r" {
+                query.descending = true;
+                i += 1;
+                continue;
+            }
+
+            if !flag.starts_with('-') {
+                if query.path.is_some() {
+                    return Err(NeocitiesErr::InvalidArgument);
+                }
+                query.path = Some(flag.trim_matches('/').to_string());
+                i += 1;
+                continue;
+            }
+
+            let value = match args.get(i + 1) {
+                Some(v) => v.as_str(),
+                None => return Err(NeocitiesErr::InvalidArgument),
+            };
+
+            match flag {
+                "--name" => match Pattern::new(value) {
+                    Ok(p) => query.name = Some(p),
+                    Err(_) => return Err(NeocitiesErr::InvalidArgument),
+                },
+                "--type" => match value {
+                    "f" | "file" => query.is_directory = Some(false),
+                    "d" | "dir" => query.is_directory = Some(true),
+                    _ => return Err(NeocitiesErr::InvalidArgument),
+                },
+                "--size" => match value.chars().next() {
+                    Some('+') => query.larger_than = Some(parse_size(&value[1..])?),
+                    Some('-') => query.smaller_than = Some(parse_size(&value[1..])?),
+                    _ => query.size = Some(parse_size(value)?),
+                },
+                "--newer" => query.newer = Some(parse_date(value, false)?),
+                "--older" => query.older = Some(parse_date(value, false)?),
+                "--sort" => {
+                    query.sort = match value {
+                        "name" => SortKey::Name,
+                        "size" => SortKey::Size,
+                        "date" => SortKey::Date,
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    }
+                }
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 2;
+        }
+
+        Ok(query)
+    }
+
+    /// Returns the files that meet every condition of the query, in the order it asks for.
+    fn select(&self, files: Vec<File>, query: &FindQuery) -> Result<Vec<File>, NeocitiesErr> {
+        let mut found: Vec<(File, Option<DateTime<Utc>>)> = Vec::new();
+
+        let prefix = query
+            .path
+            .as_ref()
+            .filter(|p| !p.is_empty())
+            .map(|p| format!("{p}/"));
+
+        for file in files.into_iter() {
+            if prefix
+                .as_ref()
+                .is_some_and(|p| !file.path.starts_with(p.as_str()))
+            {
+                continue;
+            }
+
+            let name = file.path.rsplit('/').next().unwrap_or_default();
+            if query.name.as_ref().is_some_and(|p| !p.matches(name)) {
+                continue;
+            }
+            if query.is_directory.is_some_and(|d| d != file.is_directory) {
+                continue;
+            }
+
+            // a directory has no size, so it never meets a size condition
+            if query.size.is_some() || query.larger_than.is_some() || query.smaller_than.is_some() {
+                let size = match (file.is_directory, file.size) {
+                    (false, Some(s)) => u64::try_from(s)?,
+                    _ => continue,
+                };
+                if query.size.is_some_and(|exact| size != exact) {
+                    continue;
+                }
+                if query.larger_than.is_some_and(|min| size <= min) {
+                    continue;
+                }
+                if query.smaller_than.is_some_and(|max| size >= max) {
+                    continue;
+                }
+            }
+
+            let time = file.parse_timestamp().ok().map(|t| t.with_timezone(&Utc));
+            if query.newer.is_some() || query.older.is_some() {
+                let time = match time {
+                    Some(t) => t,
+                    None => continue,
+                };
+                if query.newer.is_some_and(|newer| time <= newer) {
+                    continue;
+                }
+                if query.older.is_some_and(|older| time >= older) {
+                    continue;
+                }
+            }
+
+            found.push((file, time));
+        }
+
+        found.sort_by(|(a, a_time), (b, b_time)| {
+            let order = match query.sort {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Size => a.size.cmp(&b.size),
+                SortKey::Date => a_time.cmp(b_time),
+            };
+            order.then_with(|| a.path.cmp(&b.path))
+        });
+        if query.descending {
+            found.reverse();
+        }
+
+        Ok(found.into_iter().map(|(f, _)| f).collect())
+    }
+
+    fn write_text(
+        &self,
+        files: &[File],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        if files.is_empty() {
+            self.write("No files were found\n", &mut writer)?;
+            return Ok(());
+        }
+
+        for file in files.iter() {
+            let output = match file.is_directory {
+                true => format!(
+                    "{:>10}  \x1b[1;36m{}/\x1b[0m\x1b[90m {}\x1b[0m\n",
+                    "-", file.path, file.updated_at
+                ),
+                false => format!(
+                    "{:>10}  \x1b[1;92m{}\x1b[0m\x1b[90m {}\x1b[0m\n",
+                    format_bytes(u64::try_from(file.size.unwrap_or_default())?),
+                    file.path,
+                    file.updated_at
+                ),
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one line per file: its size in bytes (or `-` for a directory), its modification
+    /// date and its path, separated by tabs. A directory's path ends with `/`.
+    fn write_porcelain(
+        &self,
+        files: &[File],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for file in files.iter() {
+            let output = match file.is_directory {
+                true => format!("-\t{}\t{}/\n", file.updated_at, file.path),
+                false => format!(
+                    "{}\t{}\t{}\n",
+                    file.size.unwrap_or_default(),
+                    file.updated_at,
+                    file.path
+                ),
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a size given to `--size`, which unlike a rate may be zero.
+fn parse_size(value: &str) -> Result<u64, NeocitiesErr> {
+    match value.trim() {
+        "0" => Ok(0),
+        v => parse_bytes(v),
+    }
+}
+
+impl Default for Find {
+    fn default() -> Find {
+        Find::new()
+    }
+}
+
+impl Executable for Find {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let query = self.parse_opts(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        // a listing of one directory is flat, so the whole website is listed and searched from
+        // the directory
+        let list = NcList::fetch(None)?;
+        let files = self.select(list.files, &query)?;
+
+        match query.format {
+            Format::Json => {
+                let output = format!("{}\n", serde_json::to_string_pretty(&files)?);
+                self.write(output.as_str(), &mut stdout)?;
+            }
+            Format::Porcelain => self.write_porcelain(&files, &mut stdout)?,
+            Format::Text => self.write_text(&files, &mut stdout)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Search the files on your Neocities website, or in one directory of it. \
+`--name` matches the file name against a glob (e.g. '*.png'), `--type f` or `--type d` keeps only \
+files or directories, `--size +500K` keeps files larger than 500 KiB, `--size -1M` files smaller \
+than 1 MiB and `--size 0` empty files, and `--newer` and `--older` keep files changed after or before a date \
+(YYYY-MM-DD or an RFC 3339 timestamp). Results are sorted by path, or with `--sort size` or \
+`--sort date`, and `--desc` reverses the order. Use `--output json` or `--output porcelain` for \
+scripts.";
+
+const DESC_SHORT: &str = "Search files on Neocities";
+
+#[cfg(test)]
+mod tests {
+    use super::{Find, SortKey, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::File,
+        client::{command::Executable, output::Format},
+        error::NeocitiesErr,
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let f = Find::new();
+        assert_eq!(f.get_usage().contains(KEY), true);
+        assert_eq!(f.get_short_desc(), DESC_SHORT);
+        assert_eq!(f.get_long_desc(), DESC);
+    }
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    fn file(path: &str, size: Option<i64>, updated_at: &str) -> File {
+        File {
+            path: path.to_string(),
+            is_directory: size.is_none(),
+            size,
+            updated_at: updated_at.to_string(),
+            sha1_hash: None,
+        }
+    }
+
+    fn files() -> Vec<File> {
+        vec![
+            file("images", None, "Mon, 01 Jan 2024 10:00:00 -0000"),
+            file(
+                "images/big.png",
+                Some(800_000),
+                "Sat, 01 Jul 2023 10:00:00 -0000",
+            ),
+            file(
+                "images/new.png",
+                Some(900_000),
+                "Tue, 02 Jul 2024 10:00:00 -0000",
+            ),
+            file(
+                "images/small.png",
+                Some(1_000),
+                "Sun, 01 Jan 2023 10:00:00 -0000",
+            ),
+            file(
+                "index.html",
+                Some(600_000),
+                "Fri, 01 Mar 2024 10:00:00 -0000",
+            ),
+        ]
+    }
+
+    fn paths(files: &[File]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let f = Find::new();
+
+        let q = f.parse_opts(to_args(
+            "images --type f --size +500K --sort size --desc --json",
+        ))?;
+        assert_eq!(q.path.as_deref(), Some("images"));
+        assert_eq!(q.is_directory, Some(false));
+        assert_eq!(q.larger_than, Some(500 * 1024));
+        assert_eq!(q.sort, SortKey::Size);
+        assert_eq!(q.descending, true);
+        assert_eq!(q.format, Format::Json);
+
+        let q = f.parse_opts(to_args("/images/ --size 10"))?;
+        assert_eq!(q.path.as_deref(), Some("images"));
+        assert_eq!(
+            (q.size, q.larger_than, q.smaller_than),
+            (Some(10), None, None)
+        );
+        assert_eq!(f.parse_opts(to_args("--size 0"))?.size, Some(0));
+        assert_eq!(f.parse_opts(to_args("--size +0"))?.larger_than, Some(0));
+
+        assert_eq!(f.parse_opts(to_args("--type x")).is_err(), true);
+        assert_eq!(f.parse_opts(to_args("--sort color")).is_err(), true);
+        assert_eq!(f.parse_opts(to_args("--older never")).is_err(), true);
+        assert_eq!(f.parse_opts(to_args("--name")).is_err(), true);
+        assert_eq!(f.parse_opts(to_args("one two")).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn select_method() -> Result<(), NeocitiesErr> {
+        let f = Find::new();
+
+        // all PNGs over 500 KB not touched since 2024
+        let q = f.parse_opts(to_args("--name *.png --size +500K --older 2024-01-01"))?;
+        assert_eq!(paths(&f.select(files(), &q)?), vec!["images/big.png"]);
+
+        let q = f.parse_opts(to_args("--type d"))?;
+        assert_eq!(paths(&f.select(files(), &q)?), vec!["images"]);
+
+        let q = f.parse_opts(to_args("--newer 2024-01-01 --type f --sort date --desc"))?;
+        assert_eq!(
+            paths(&f.select(files(), &q)?),
+            vec!["images/new.png", "index.html"]
+        );
+
+        let q = f.parse_opts(to_args("--type f --size -700K --sort size"))?;
+        assert_eq!(
+            paths(&f.select(files(), &q)?),
+            vec!["images/small.png", "index.html"]
+        );
+
+        let q = f.parse_opts(vec![])?;
+        assert_eq!(f.select(files(), &q)?.len(), 5);
+
+        // a directory is searched beneath it, without the directory itself
+        let q = f.parse_opts(to_args("images --sort size"))?;
+        assert_eq!(
+            paths(&f.select(files(), &q)?),
+            vec!["images/small.png", "images/big.png", "images/new.png"]
+        );
+
+        let q = f.parse_opts(to_args("--size 1000"))?;
+        assert_eq!(paths(&f.select(files(), &q)?), vec!["images/small.png"]);
+
+        let mut with_empty = files();
+        with_empty.push(file(
+            "empty.txt",
+            Some(0),
+            "Fri, 01 Mar 2024 10:00:00 -0000",
+        ));
+        let q = f.parse_opts(to_args("--size 0"))?;
+        assert_eq!(paths(&f.select(with_empty, &q)?), vec!["empty.txt"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_methods() -> Result<(), NeocitiesErr> {
+        let f = Find::new();
+        let found = vec![
+            file("images", None, "Mon, 01 Jan 2024 10:00:00 -0000"),
+            file(
+                "images/big.png",
+                Some(800_000),
+                "Sat, 01 Jul 2023 10:00:00 -0000",
+            ),
+        ];
+
+        let mut output = Vec::new();
+        f.write_text(&found, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("781.2 KiB"), true);
+        assert_eq!(s.contains("images/"), true);
+
+        let mut output = Vec::new();
+        f.write_porcelain(&found, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "-\tMon, 01 Jan 2024 10:00:00 -0000\timages/\n\
+             800000\tSat, 01 Jul 2023 10:00:00 -0000\timages/big.png\n"
+        );
+
+        let mut output = Vec::new();
+        f.write_text(&[], &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "No files were found\n");
+
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use super::{
+    apply, cat,
     command::{Command, CommandKind, Executable},
-    delete, diff, help, info, key, list, upload, version,
+    delete, diff, du, edit, find, help, info, init, key, list, log, mv, plan, rollback, serve,
+    stat, trash, upload, usage, version, watch,
 };
 use crate::error::NeocitiesErr;
 
@@ -74,6 +76,21 @@ impl Help {
             delete::KEY => Ok(Command::new(CommandKind::Delete)),
             key::KEY => Ok(Command::new(CommandKind::Key)),
             diff::KEY => Ok(Command::new(CommandKind::Diff)),
+            mv::KEY => Ok(Command::new(CommandKind::Move)),
+            plan::KEY => Ok(Command::new(CommandKind::Plan)),
+            apply::KEY => Ok(Command::new(CommandKind::Apply)),
+            trash::KEY => Ok(Command::new(CommandKind::Trash)),
+            log::KEY => Ok(Command::new(CommandKind::Log)),
+            rollback::KEY => Ok(Command::new(CommandKind::Rollback)),
+            usage::KEY => Ok(Command::new(CommandKind::Usage)),
+            find::KEY => Ok(Command::new(CommandKind::Find)),
+            du::KEY => Ok(Command::new(CommandKind::Du)),
+            stat::KEY => Ok(Command::new(CommandKind::Stat)),
+            cat::KEY => Ok(Command::new(CommandKind::Cat)),
+            edit::KEY => Ok(Command::new(CommandKind::Edit)),
+            watch::KEY => Ok(Command::new(CommandKind::Watch)),
+            serve::KEY => Ok(Command::new(CommandKind::Serve)),
+            init::KEY => Ok(Command::new(CommandKind::Init)),
             help::KEY => Ok(Command::new(CommandKind::Help)),
             version::KEY => Ok(Command::new(CommandKind::Version)),
             _ => Err(NeocitiesErr::InvalidCommand),
@@ -125,6 +142,21 @@ Commands:
 \x1b[1;32mkey\x1b[0m       Neocities API key
 \x1b[1;32mlist\x1b[0m      List files on Neocities
 \x1b[1;32mdiff\x1b[0m      Compare local with remote paths
+\x1b[1;32mmv\x1b[0m        Move files on Neocities
+\x1b[1;32mplan\x1b[0m      Plan a deployment
+\x1b[1;32mapply\x1b[0m     Apply a deployment plan
+\x1b[1;32mtrash\x1b[0m     List and restore deleted files
+\x1b[1;32mlog\x1b[0m       Show deployment history
+\x1b[1;32mrollback\x1b[0m  Roll back to a deployment
+\x1b[1;32musage\x1b[0m     Show disk usage and free space
+\x1b[1;32mfind\x1b[0m      Search files on Neocities
+\x1b[1;32mdu\x1b[0m        Show disk usage per directory
+\x1b[1;32mstat\x1b[0m      Show details of remote files
+\x1b[1;32mcat\x1b[0m       Print remote files to stdout
+\x1b[1;32medit\x1b[0m      Edit a remote file in $EDITOR
+\x1b[1;32mwatch\x1b[0m     Deploy local changes as they happen
+\x1b[1;32mserve\x1b[0m     Preview a directory locally
+\x1b[1;32minit\x1b[0m      Set up a new project
 \x1b[1;32mversion\x1b[0m   Show neocities client version
 
 Help for a specific command:
@@ -136,7 +168,10 @@ Help for a specific command:
 mod tests {
     use super::{Help, DESC, DESC_SHORT, HELP_MSG, KEY, NC_ASCII_BANNER};
     use crate::{
-        client::{command::Executable, delete, info, key, list, upload, version},
+        client::{
+            apply, cat, command::Executable, delete, du, edit, find, info, init, key, list, log,
+            mv, plan, rollback, serve, stat, trash, upload, usage, version, watch,
+        },
         error::NeocitiesErr,
     };
 
@@ -171,13 +206,28 @@ mod tests {
         Ok(())
     }
 
-    const COMMANDS: [&str; 6] = [
+    const COMMANDS: [&str; 21] = [
         version::KEY,
         info::KEY,
         key::KEY,
         list::KEY,
         upload::KEY,
         delete::KEY,
+        mv::KEY,
+        plan::KEY,
+        apply::KEY,
+        trash::KEY,
+        log::KEY,
+        rollback::KEY,
+        usage::KEY,
+        find::KEY,
+        du::KEY,
+        stat::KEY,
+        cat::KEY,
+        edit::KEY,
+        watch::KEY,
+        serve::KEY,
+        init::KEY,
     ];
 
     #[test]
@@ -0,0 +1,193 @@
+use crate::error::NeocitiesErr;
+use glob::{MatchOptions, Pattern};
+use std::{
+    env, fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// The name of the file that lists the local paths that are not part of the website
+pub const IGNORE_FILE: &str = ".neocitiesignore";
+
+/// The rules of a `.neocitiesignore`, which leave local paths out of uploads and comparisons
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Ignore {
+    /// The directory that holds the ignore file, which the rules are relative to
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+/// A line of an ignore file
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    pattern: Pattern,
+    /// The rule ends with `/`, and only matches directories
+    dir_only: bool,
+    /// The rule contains a `/`, and is matched against the whole path from the base rather than
+    /// the name of each file
+    anchored: bool,
+}
+
+impl Ignore {
+    /// Reads the ignore file in a directory. A directory without one ignores nothing.
+    pub fn load(dir: &Path) -> Result<Ignore, NeocitiesErr> {
+        let path = dir.join(IGNORE_FILE);
+        let contents = match path.is_file() {
+            true => fs::read_to_string(path)?,
+            false => String::new(),
+        };
+        Ignore::parse(dir, &contents)
+    }
+
+    /// Parses the contents of an ignore file: one glob per line, with blank lines and lines that
+    /// start with `#` skipped. A glob with a `/` matches a path from the base, and one without
+    /// matches a file or directory of that name anywhere beneath it.
+    pub fn parse(base: &Path, contents: &str) -> Result<Ignore, NeocitiesErr> {
+        let mut rules: Vec<Rule> = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let dir_only = line.ends_with('/');
+            let glob = line.trim_end_matches('/');
+            let anchored = glob.contains('/');
+            let pattern = match Pattern::new(glob.trim_start_matches('/')) {
+                Ok(p) => p,
+                Err(_) => return Err(NeocitiesErr::InvalidArgument),
+            };
+
+            rules.push(Rule {
+                pattern,
+                dir_only,
+                anchored,
+            });
+        }
+
+        Ok(Ignore {
+            base: absolute(base),
+            rules,
+        })
+    }
+
+    /// Returns true if a path, or a directory above it, matches a rule. Paths outside the base
+    /// are never ignored.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.is_ignored_as(path, path.is_dir())
+    }
+
+    /// Returns true if a path matches a rule, like `is_ignored`, for a path that may not exist
+    /// locally, such as a file on the website, so whether it is a directory is given.
+    pub fn is_ignored_as(&self, path: &Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let relative = match absolute(path).strip_prefix(&self.base) {
+            Ok(r) => r.to_path_buf(),
+            Err(_) => return false,
+        };
+        let parts: Vec<&str> = relative.iter().filter_map(|c| c.to_str()).collect();
+
+        // every directory above the path is checked too, since ignoring a directory ignores
+        // everything in it
+        (1..=parts.len()).any(|n| {
+            let is_dir = n < parts.len() || is_dir;
+            self.matches(&parts[..n].join("/"), parts[n - 1], is_dir)
+        })
+    }
+
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        self.rules.iter().any(|r| {
+            (is_dir || !r.dir_only)
+                && match r.anchored {
+                    true => r.pattern.matches_with(relative, options),
+                    false => r.pattern.matches_with(name, options),
+                }
+        })
+    }
+}
+
+/// Returns a path from the root of the file system, without resolving symbolic links, so that
+/// paths given relative to the working directory can be compared with the base.
+fn absolute(path: &Path) -> PathBuf {
+    let joined = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => env::current_dir().unwrap_or_default().join(path),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ignore, IGNORE_FILE};
+    use crate::error::NeocitiesErr;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn is_ignored_method() -> Result<(), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join(".git"))?;
+        fs::create_dir_all(base.join("blog/drafts"))?;
+        fs::write(base.join("tmp"), "a file, not a directory")?;
+
+        let ignore = Ignore::parse(
+            base,
+            "# comment\n\n.git/\ntmp/\nneocities.json\n*.swp\n/drafts\n",
+        )?;
+        let ignored = |p: &str| ignore.is_ignored(&base.join(p));
+
+        assert_eq!(ignored("neocities.json"), true);
+        assert_eq!(ignored("blog/neocities.json"), true);
+        assert_eq!(ignored(".git"), true);
+        assert_eq!(ignored(".git/config"), true);
+        assert_eq!(ignored("blog/notes.swp"), true);
+        assert_eq!(ignored("drafts/post.html"), true);
+
+        // an anchored rule only matches from the base, and a directory rule only directories
+        assert_eq!(ignored("blog/drafts"), false);
+        assert_eq!(ignored("tmp"), false);
+        assert_eq!(ignored("index.html"), false);
+        assert_eq!(
+            ignore.is_ignored(Path::new("/elsewhere/neocities.json")),
+            false
+        );
+
+        // a path that does not exist locally is matched as the kind of path it is said to be
+        assert_eq!(ignore.is_ignored_as(&base.join("blog/tmp"), true), true);
+        assert_eq!(ignore.is_ignored_as(&base.join("blog/tmp"), false), false);
+
+        assert_eq!(Ignore::parse(base, "[").is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_function() -> Result<(), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        assert_eq!(Ignore::load(temp_dir.path())?.rules.is_empty(), true);
+
+        fs::write(temp_dir.path().join(IGNORE_FILE), "*.swp\n")?;
+        let ignore = Ignore::load(temp_dir.path())?;
+        assert_eq!(ignore.is_ignored(&temp_dir.path().join("a.swp")), true);
+
+        Ok(())
+    }
+}
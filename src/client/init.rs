@@ -0,0 +1,590 @@
+use super::{
+    command::Executable,
+    ignore::IGNORE_FILE,
+    state,
+    trash::{self, public_url},
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        http::get_file,
+        list::{ListResponse, NcList},
+    },
+    error::NeocitiesErr,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "init";
+
+/// The name of the project-level config file, at the top of a project
+pub const CONFIG_FILE: &str = "neocities.json";
+
+/// Scaffolds a new Neocities project in a local directory
+pub struct Init {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The settings of a project, kept in `neocities.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// The sitename of the Neocities website the project deploys to
+    pub sitename: String,
+    /// The local directory, relative to the project, whose contents make up the website
+    pub local_root: String,
+    /// The directory on the website the local root is deployed to, or an empty string for the
+    /// root of the website
+    pub remote_root: String,
+}
+
+impl ProjectConfig {
+    /// Reads the config of the project in a directory.
+    pub fn load(dir: &Path) -> Result<ProjectConfig, NeocitiesErr> {
+        let contents = fs::read_to_string(dir.join(CONFIG_FILE))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Returns the local root of the project in the current directory, or the current directory
+    /// itself if it holds no project.
+    pub fn local_root() -> Result<PathBuf, NeocitiesErr> {
+        match Path::new(CONFIG_FILE).is_file() {
+            true => Ok(PathBuf::from(
+                ProjectConfig::load(Path::new("."))?.local_root,
+            )),
+            false => Ok(PathBuf::from(".")),
+        }
+    }
+
+    /// Writes the config to the project in a directory.
+    pub fn save(&self, dir: &Path) -> Result<(), NeocitiesErr> {
+        let contents = format!("{}\n", serde_json::to_string_pretty(self)?);
+        fs::write(dir.join(CONFIG_FILE), contents)?;
+        Ok(())
+    }
+}
+
+/// The options given to `init`
+#[derive(Debug, PartialEq)]
+struct InitOpts {
+    dir: PathBuf,
+    sitename: Option<String>,
+    local_root: String,
+    remote_root: String,
+    /// Create a starter `index.html`, `404.html` and `style.css`
+    starter: bool,
+    /// Pull the current contents of the website, or ask if `None`
+    link: Option<bool>,
+    /// Replace an existing config
+    force: bool,
+}
+
+impl Init {
+    /// A constructor that returns an instance of `Init`.
+    pub fn new() -> Init {
+        Init {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m [<dir>] [--sitename <name>] [--local-root <dir>] [--remote-root <dir>] [--starter] [--link | --no-link] [--force]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<InitOpts, NeocitiesErr> {
+        let mut opts = InitOpts {
+            dir: PathBuf::from("."),
+            sitename: None,
+            local_root: String::from("."),
+            remote_root: String::new(),
+            starter: false,
+            link: None,
+            force: false,
+        };
+        let mut dir: Option<PathBuf> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--starter" => opts.starter = true,
+                "--link" => opts.link = Some(true),
+                "--no-link" => opts.link = Some(false),
+                "--force" => opts.force = true,
+                flag @ ("--sitename" | "--local-root" | "--remote-root") => {
+                    let value = match args.get(i + 1) {
+                        Some(v) => v.clone(),
+                        None => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    match flag {
+                        "--sitename" => opts.sitename = Some(value),
+                        "--local-root" => opts.local_root = value,
+                        _ => opts.remote_root = value.trim_matches('/').to_string(),
+                    }
+                    i += 1;
+                }
+                a if !a.starts_with('-') && dir.is_none() => dir = Some(PathBuf::from(a)),
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 1;
+        }
+
+        if let Some(d) = dir {
+            opts.dir = d;
+        }
+
+        Ok(opts)
+    }
+
+    /// Returns the sitename to write to the config: the one given, otherwise the username in the
+    /// environment, otherwise the name of the project directory.
+    fn sitename(&self, opts: &InitOpts) -> Result<String, NeocitiesErr> {
+        if let Some(s) = &opts.sitename {
+            return Ok(s.clone());
+        }
+        if let Some(user) = Credentials::new().get_username() {
+            return Ok(user);
+        }
+
+        // the directory is only created once the scaffold is written
+        let dir = match opts.dir.exists() {
+            true => fs::canonicalize(&opts.dir)?,
+            false => opts.dir.clone(),
+        };
+        match dir.file_name().and_then(|n| n.to_str()) {
+            Some(n) => Ok(n.to_string()),
+            None => Err(NeocitiesErr::InvalidArgument),
+        }
+    }
+
+    /// Writes a file unless one is already there, and says which it did.
+    fn create(
+        &self,
+        path: &Path,
+        contents: &str,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let output = match path.exists() {
+            true => format!("Kept the existing {}\n", path.display()),
+            false => {
+                fs::write(path, contents)?;
+                format!("\x1b[92mCreated\x1b[0m {}\n", path.display())
+            }
+        };
+        self.write(output.as_str(), &mut writer)
+    }
+
+    /// Downloads the files of the website under the remote root into the local root, with
+    /// `fetch` getting the contents at a URL. Files that already exist locally are kept. Returns
+    /// the number of files downloaded.
+    fn pull(
+        &self,
+        list: &ListResponse,
+        config: &ProjectConfig,
+        local_root: &Path,
+        fetch: impl Fn(String) -> Result<Vec<u8>, NeocitiesErr>,
+        mut writer: impl std::io::Write,
+    ) -> Result<usize, NeocitiesErr> {
+        let mut pulled = 0;
+
+        for file in list.files.iter().filter(|f| !f.is_directory) {
+            let remote = file.path.trim_matches('/');
+            let relative = match config.remote_root.as_str() {
+                "" => remote,
+                root => match remote.strip_prefix(root).and_then(|r| r.strip_prefix('/')) {
+                    Some(r) => r,
+                    None => continue,
+                },
+            };
+
+            let local = local_root.join(relative);
+            if local.exists() {
+                let output = format!("Kept the existing {}\n", local.display());
+                self.write(output.as_str(), &mut writer)?;
+                continue;
+            }
+
+            let contents = fetch(public_url(&config.sitename, remote))?;
+            if let Some(parent) = local.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&local, contents)?;
+            pulled += 1;
+
+            let output = format!("\x1b[92mPulled\x1b[0m {}\n", local.display());
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(pulled)
+    }
+
+    /// Asks the user whether to pull the current contents of the website. A closed stdin
+    /// declines.
+    fn confirm(
+        &self,
+        sitename: &str,
+        mut reader: impl BufRead,
+        mut writer: impl std::io::Write,
+    ) -> Result<bool, NeocitiesErr> {
+        loop {
+            let question = format!("Pull the current contents of {sitename} into it? [y/N] ");
+            self.write(question.as_str(), &mut writer)?;
+            writer.flush()?;
+
+            let mut input = String::new();
+            if reader.read_line(&mut input)? == 0 {
+                self.write("\n", &mut writer)?;
+                return Ok(false);
+            }
+
+            match input.trim() {
+                "Y" | "y" => return Ok(true),
+                "N" | "n" | "" => return Ok(false),
+                other => {
+                    let err = format!("Invalid input: '{other}'. Please try again.\n");
+                    self.write(err.as_str(), &mut writer)?;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Init {
+    fn default() -> Init {
+        Init::new()
+    }
+}
+
+impl Executable for Init {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let opts = self.parse_opts(args)?;
+
+        if opts.dir.join(CONFIG_FILE).exists() && !opts.force {
+            return Err(NeocitiesErr::FileExists);
+        }
+
+        // everything that can stop the scaffold is settled before anything is written
+        let mut sitename = self.sitename(&opts)?;
+        let link = match opts.link {
+            Some(l) => l,
+            None => {
+                let stdin = io::stdin();
+                Credentials::have_env_vars()
+                    && stdin.is_terminal()
+                    && self.confirm(&sitename, stdin.lock(), &mut stdout)?
+            }
+        };
+        if link {
+            if !Credentials::have_env_vars() {
+                self.write(ENV_VAR_MSG, &mut stdout)?;
+                return Ok(());
+            }
+
+            // the files are pulled from the website of the account, so the project must deploy
+            // to that website
+            let account = trash::sitename()?;
+            if opts.sitename.as_ref().is_some_and(|s| *s != account) {
+                return Err(NeocitiesErr::SitenameMismatch);
+            }
+            sitename = account;
+        }
+
+        fs::create_dir_all(&opts.dir)?;
+
+        let config = ProjectConfig {
+            sitename,
+            local_root: opts.local_root.clone(),
+            remote_root: opts.remote_root.clone(),
+        };
+        config.save(&opts.dir)?;
+        let output = format!(
+            "\x1b[92mCreated\x1b[0m {}\n",
+            opts.dir.join(CONFIG_FILE).display()
+        );
+        self.write(output.as_str(), &mut stdout)?;
+
+        self.create(&opts.dir.join(IGNORE_FILE), &default_ignore(), &mut stdout)?;
+
+        let local_root = opts.dir.join(&opts.local_root);
+        fs::create_dir_all(&local_root)?;
+
+        if link {
+            let list = NcList::fetch(None)?;
+            let pulled = self.pull(&list, &config, &local_root, get_file, &mut stdout)?;
+            let output = format!("Pulled {pulled} files from {}\n", config.sitename);
+            self.write(output.as_str(), &mut stdout)?;
+        }
+
+        if opts.starter {
+            self.create(&local_root.join("index.html"), STARTER_INDEX, &mut stdout)?;
+            self.create(&local_root.join("404.html"), STARTER_404, &mut stdout)?;
+            self.create(&local_root.join("style.css"), STARTER_STYLE, &mut stdout)?;
+        }
+
+        let output = format!(
+            "Initialized a Neocities project for {} in {}\n",
+            config.sitename,
+            opts.dir.display()
+        );
+        self.write(output.as_str(), &mut stdout)?;
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+/// Returns the contents of a new `.neocitiesignore`: the state directory, the project files and
+/// the clutter that editors and tools leave behind.
+fn default_ignore() -> String {
+    format!(
+        "# Local paths that are not part of the website, one glob per line\n\
+         {}/\n{CONFIG_FILE}\n{IGNORE_FILE}\n.git/\nnode_modules/\n.DS_Store\nThumbs.db\n*.swp\n*~\n",
+        state::DEFAULT_STATE_DIR
+    )
+}
+
+const STARTER_INDEX: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+  <head>
+    <meta charset=\"utf-8\">
+    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+    <title>My Neocities website</title>
+    <link href=\"/style.css\" rel=\"stylesheet\">
+  </head>
+  <body>
+    <h1>Welcome to my website!</h1>
+    <p>This is a new website on Neocities.</p>
+  </body>
+</html>
+";
+
+const STARTER_404: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+  <head>
+    <meta charset=\"utf-8\">
+    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+    <title>Page not found</title>
+    <link href=\"/style.css\" rel=\"stylesheet\">
+  </head>
+  <body>
+    <h1>Page not found</h1>
+    <p>The page you were looking for does not exist. <a href=\"/\">Go home</a>.</p>
+  </body>
+</html>
+";
+
+const STARTER_STYLE: &str = "body {
+  max-width: 40rem;
+  margin: 2rem auto;
+  padding: 0 1rem;
+  font-family: sans-serif;
+  line-height: 1.5;
+}
+";
+
+const DESC: &str = "Set up a Neocities project in a directory (the current one by default). It \
+writes neocities.json, with the sitename, the local directory that holds the website and the \
+directory on the website it deploys to, and a default .neocitiesignore, whose paths are left out \
+when directories are uploaded, compared, watched or served from the project. `--starter` adds an \
+index.html, a 404.html and a style.css. To start from the site of your credentials, `--link` \
+pulls its current files into the local directory; when run in a terminal with your credentials \
+set, `init` asks. Existing files are never overwritten.";
+
+const DESC_SHORT: &str = "Set up a new project";
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        default_ignore, Init, ProjectConfig, CONFIG_FILE, DESC, DESC_SHORT, IGNORE_FILE, KEY,
+    };
+    use crate::{
+        api::list::{File, ListResponse},
+        client::command::Executable,
+        error::NeocitiesErr,
+    };
+    use std::{cell::RefCell, fs, path::PathBuf};
+
+    #[test]
+    fn usage_desc_methods() {
+        let i = Init::new();
+        assert_eq!(i.get_usage().contains(KEY), true);
+        assert_eq!(i.get_short_desc(), DESC_SHORT);
+        assert_eq!(i.get_long_desc(), DESC);
+    }
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let i = Init::new();
+
+        let opts = i.parse_opts(to_args(
+            "mysite --sitename foo --local-root public --remote-root /blog/ --starter --no-link",
+        ))?;
+        assert_eq!(opts.dir, PathBuf::from("mysite"));
+        assert_eq!(opts.sitename.as_deref(), Some("foo"));
+        assert_eq!(opts.local_root, "public");
+        assert_eq!(opts.remote_root, "blog");
+        assert_eq!(opts.starter, true);
+        assert_eq!(opts.link, Some(false));
+        assert_eq!(opts.force, false);
+
+        let opts = i.parse_opts(vec![])?;
+        assert_eq!((opts.local_root.as_str(), opts.link), (".", None));
+
+        assert_eq!(i.parse_opts(to_args("--sitename")).is_err(), true);
+        assert_eq!(i.parse_opts(to_args("one two")).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_and_create_methods() -> Result<(), NeocitiesErr> {
+        let i = Init::new();
+        let temp_dir = tempfile::tempdir()?;
+
+        let config = ProjectConfig {
+            sitename: String::from("foo"),
+            local_root: String::from("public"),
+            remote_root: String::new(),
+        };
+        config.save(temp_dir.path())?;
+        assert_eq!(ProjectConfig::load(temp_dir.path())?, config);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(CONFIG_FILE))?
+                .contains("\"sitename\": \"foo\""),
+            true
+        );
+
+        let ignore = temp_dir.path().join(IGNORE_FILE);
+        let mut output = Vec::new();
+        i.create(&ignore, &default_ignore(), &mut output)?;
+        assert_eq!(fs::read_to_string(&ignore)?.contains(".neocities/\n"), true);
+
+        // an existing file is kept
+        fs::write(&ignore, "custom\n")?;
+        i.create(&ignore, &default_ignore(), &mut output)?;
+        assert_eq!(fs::read_to_string(&ignore)?, "custom\n");
+        assert_eq!(
+            String::from_utf8(output)?.contains("Kept the existing"),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pull_method() -> Result<(), NeocitiesErr> {
+        let i = Init::new();
+        let temp_dir = tempfile::tempdir()?;
+        let local_root = temp_dir.path().join("public");
+        fs::create_dir_all(&local_root)?;
+        fs::write(local_root.join("index.html"), "mine")?;
+
+        let file = |path: &str, is_directory: bool| File {
+            path: path.to_string(),
+            is_directory,
+            size: None,
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: None,
+        };
+        let list = ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("blog", true),
+                file("blog/index.html", false),
+                file("blog/posts/first post.html", false),
+                file("index.html", false),
+            ],
+        };
+        let config = ProjectConfig {
+            sitename: String::from("foo"),
+            local_root: String::from("public"),
+            remote_root: String::from("blog"),
+        };
+
+        let fetched = RefCell::new(Vec::new());
+        let fetch = |url: String| {
+            fetched.borrow_mut().push(url.clone());
+            Ok(url.into_bytes())
+        };
+
+        // blog/index.html maps to the local index.html, which is kept
+        let mut output = Vec::new();
+        assert_eq!(i.pull(&list, &config, &local_root, fetch, &mut output)?, 1);
+        assert_eq!(
+            fetched.into_inner(),
+            vec!["https://foo.neocities.org/blog/posts/first%20post.html"]
+        );
+        assert_eq!(
+            fs::read_to_string(local_root.join("posts/first post.html"))?,
+            "https://foo.neocities.org/blog/posts/first%20post.html"
+        );
+        assert_eq!(fs::read_to_string(local_root.join("index.html"))?, "mine");
+        assert_eq!(
+            String::from_utf8(output)?.contains("Kept the existing"),
+            true
+        );
+
+        // with the whole website, the files outside the blog are pulled too
+        let config = ProjectConfig {
+            remote_root: String::new(),
+            ..config
+        };
+        let mut output = Vec::new();
+        assert_eq!(
+            i.pull(&list, &config, &local_root, |_| Ok(Vec::new()), &mut output)?,
+            2
+        );
+        assert_eq!(
+            local_root.join("blog/posts/first post.html").is_file(),
+            true
+        );
+        assert_eq!(fs::read_to_string(local_root.join("index.html"))?, "mine");
+
+        Ok(())
+    }
+
+    #[test]
+    fn confirm_method() -> Result<(), NeocitiesErr> {
+        let i = Init::new();
+
+        let mut output = Vec::new();
+        assert_eq!(
+            i.confirm("foo", "maybe\ny\n".as_bytes(), &mut output)?,
+            true
+        );
+        assert_eq!(String::from_utf8(output)?.contains("Invalid input"), true);
+
+        let mut output = Vec::new();
+        assert_eq!(i.confirm("foo", "\n".as_bytes(), &mut output)?, false);
+        assert_eq!(i.confirm("foo", "".as_bytes(), &mut output)?, false);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use super::{diff::Diff, state};
+use crate::{api::list::File, error::NeocitiesErr};
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// The name of the journal file, inside the state directory
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// The number of bytes read at a time from the end of the journal to find the last entry
+const TAIL_CHUNK: u64 = 4096;
+
+/// A file changed by an operation recorded in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalFile {
+    /// The path of the file on the website
+    pub path: String,
+    /// The byte size of the file
+    pub size: Option<i64>,
+    /// The SHA-1 hash of the file
+    pub sha1_hash: Option<String>,
+}
+
+impl JournalFile {
+    /// Describes a local file that is uploaded to the same path on the website.
+    pub fn from_local(path: &str) -> Result<JournalFile, NeocitiesErr> {
+        let diff = Diff::new();
+        let path_buf = PathBuf::from(path);
+
+        Ok(JournalFile {
+            path: diff.format_path(&path_buf)?,
+            size: Some(i64::try_from(fs::metadata(&path_buf)?.len())?),
+            sha1_hash: Some(diff.hash(&path_buf)?),
+        })
+    }
+}
+
+impl From<&File> for JournalFile {
+    fn from(file: &File) -> JournalFile {
+        JournalFile {
+            path: file.path.clone(),
+            size: file.size,
+            sha1_hash: file.sha1_hash.clone(),
+        }
+    }
+}
+
+/// A single operation that changed a Neocities website, as recorded in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// A sequential number that identifies the entry
+    pub id: u64,
+    /// The kind of change, either `upload` or `delete`
+    pub operation: String,
+    /// The command that made the change, such as `upload` or `apply`
+    pub command: String,
+    /// An RFC 3339 timestamp for the change
+    pub timestamp: String,
+    /// The sitename of the website that was changed
+    pub site: Option<String>,
+    /// The local user who made the change
+    pub user: Option<String>,
+    /// The commit checked out in the current git repository, if any
+    pub git_head: Option<String>,
+    /// The files that were changed
+    pub files: Vec<JournalFile>,
+    /// The status returned by the server
+    pub result: String,
+    /// The type of error returned by the server, if any
+    pub error_type: Option<String>,
+    /// The message returned by the server
+    pub message: String,
+}
+
+impl JournalEntry {
+    /// Creates an entry for an operation on a site, filling in the time, user and git HEAD. The
+    /// server's response is added with `respond`.
+    pub fn new(
+        operation: &str,
+        command: &str,
+        site: Option<String>,
+        files: Vec<JournalFile>,
+    ) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            operation: operation.to_string(),
+            command: command.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            site,
+            user: env::var("USER").or_else(|_| env::var("USERNAME")).ok(),
+            git_head: git_head(),
+            files,
+            result: String::new(),
+            error_type: None,
+            message: String::new(),
+        }
+    }
+
+    /// Adds the server's response to the entry.
+    pub fn respond(mut self, result: &str, error_type: Option<String>, message: &str) -> Self {
+        self.result = result.to_string();
+        self.error_type = error_type;
+        self.message = message.to_string();
+        self
+    }
+
+    /// Returns the location of the journal.
+    pub fn path() -> PathBuf {
+        state::dir().join(JOURNAL_FILE)
+    }
+
+    /// Appends the entry to the journal as a line of JSON, numbering it after the last entry.
+    /// Existing entries are never rewritten.
+    pub fn append(mut self) -> Result<JournalEntry, NeocitiesErr> {
+        self.id = JournalEntry::last_id()? + 1;
+
+        fs::create_dir_all(state::dir())?;
+        let mut journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(JournalEntry::path())?;
+
+        let line = format!("{}\n", serde_json::to_string(&self)?);
+        journal.write_all(line.as_bytes())?;
+
+        Ok(self)
+    }
+
+    /// Returns the id of the last entry in the journal, or 0 if it is empty. The journal is read
+    /// from its end, so that appending does not parse every entry before it.
+    fn last_id() -> Result<u64, NeocitiesErr> {
+        let path = JournalEntry::path();
+        if !path.is_file() {
+            return Ok(0);
+        }
+
+        let mut journal = fs::File::open(path)?;
+        let mut pos = journal.metadata()?.len();
+        let mut tail: Vec<u8> = Vec::new();
+
+        loop {
+            let end = tail
+                .iter()
+                .rposition(|b| !b.is_ascii_whitespace())
+                .map_or(0, |i| i + 1);
+            let start = tail[..end].iter().rposition(|b| *b == b'\n');
+
+            if start.is_some() || pos == 0 {
+                let line = &tail[start.map_or(0, |i| i + 1)..end];
+                if line.is_empty() {
+                    return Ok(0);
+                }
+                let last: JournalEntry = serde_json::from_slice(line)?;
+                return Ok(last.id);
+            }
+
+            let step = pos.min(TAIL_CHUNK);
+            pos -= step;
+            let mut chunk = vec![0; usize::try_from(step)?];
+            journal.seek(SeekFrom::Start(pos))?;
+            journal.read_exact(&mut chunk)?;
+            chunk.extend(tail);
+            tail = chunk;
+        }
+    }
+
+    /// Reads every entry in the journal, oldest first.
+    pub fn read_all() -> Result<Vec<JournalEntry>, NeocitiesErr> {
+        let path = JournalEntry::path();
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in fs::read_to_string(path)?.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(line)?);
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Returns the commit checked out in the git repository that contains the current working
+/// directory, or `None` if there is no repository or git is not installed.
+fn git_head() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let head = String::from_utf8(output.stdout).ok()?;
+    Some(head.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JournalEntry, JournalFile};
+    use crate::{client::state::TempStateDir, error::NeocitiesErr};
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    fn from_local_method() -> Result<(), NeocitiesErr> {
+        let f = JournalFile::from_local("./tests/fixtures/foo.html")?;
+        assert_eq!(f.path, "tests/fixtures/foo.html");
+        assert_eq!(
+            f.size,
+            Some(fs::metadata("tests/fixtures/foo.html")?.len() as i64)
+        );
+        assert_eq!(f.sha1_hash.map(|s| s.len()), Some(40));
+        Ok(())
+    }
+
+    #[test]
+    #[serial(env)]
+    fn append_and_read_all() -> Result<(), NeocitiesErr> {
+        let _state_dir = TempStateDir::new()?;
+
+        assert_eq!(JournalEntry::read_all()?.is_empty(), true);
+
+        let file = JournalFile {
+            path: String::from("foo.html"),
+            size: Some(3),
+            sha1_hash: Some(String::from("abc")),
+        };
+
+        let first = JournalEntry::new(
+            "upload",
+            "upload",
+            Some(String::from("foo")),
+            vec![file.clone()],
+        )
+        .respond(
+            "success",
+            None,
+            "your file(s) have been successfully uploaded",
+        )
+        .append()?;
+        let second = JournalEntry::new("delete", "delete", None, vec![file.clone()])
+            .respond("success", None, "file(s) have been deleted")
+            .append()?;
+
+        assert_eq!(first.id, 1);
+        assert_eq!(first.site.as_deref(), Some("foo"));
+        assert_eq!(second.id, 2);
+        assert_eq!(JournalEntry::read_all()?, vec![first, second]);
+
+        // the last id is found from the end of the journal, even past a long entry
+        let third = JournalEntry::new("upload", "upload", None, vec![file; 200])
+            .respond("success", None, "")
+            .append()?;
+        assert_eq!(third.id, 3);
+        assert_eq!(JournalEntry::last_id()?, 3);
+
+        Ok(())
+    }
+}
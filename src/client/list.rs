@@ -1,4 +1,8 @@
-use super::command::Executable;
+use super::{
+    command::Executable,
+    output::format_bytes,
+    tree::{Node, RemoteTree},
+};
 use crate::{
     api::{
         credentials::{Credentials, ENV_VAR_MSG},
@@ -23,7 +27,9 @@ impl List {
     /// A constructor that returns an instance of `List`
     pub fn new() -> List {
         List {
-            usage: String::from(format!("\x1b[1;32m{KEY}\x1b[0m /path")),
+            usage: String::from(format!(
+                "\x1b[1;32m{KEY}\x1b[0m [-d] [--tree] [-L|--depth <n>] /path"
+            )),
             short: String::from(DESC_SHORT),
             long: String::from(DESC),
             dir_color: "\x1b[1;36m",
@@ -56,6 +62,116 @@ impl List {
         (is_detailed, path)
     }
 
+    /// Removes the `--tree` and `--depth <n>` flags from the arguments. It returns `None` if the
+    /// listing is not a tree, or the depth to which a tree is drawn, which is unlimited if no
+    /// depth is given. `--depth` on its own also asks for a tree.
+    fn take_tree_opts(
+        &self,
+        args: &mut Vec<String>,
+    ) -> Result<Option<Option<usize>>, NeocitiesErr> {
+        let mut is_tree = false;
+        let mut depth: Option<usize> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "-t" | "--tree" => {
+                    is_tree = true;
+                    args.remove(i);
+                }
+                "-L" | "--depth" => {
+                    depth = match args.get(i + 1).map(|n| n.parse::<usize>()) {
+                        Some(Ok(n)) if n > 0 => Some(n),
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    is_tree = true;
+                    args.drain(i..i + 2);
+                }
+                _ => i += 1,
+            }
+        }
+
+        Ok(is_tree.then_some(depth))
+    }
+
+    /// Draws the files beneath a node like `tree(1)` does, with the total size of each directory,
+    /// down to the given depth.
+    fn output_tree(
+        &self,
+        node: &Node,
+        depth: Option<usize>,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let name = match node.path.as_str() {
+            "" => String::from("."),
+            p => format!("{p}/"),
+        };
+        let output = format!(
+            "{}{name}\x1b[0m\x1b[90m ({})\x1b[0m\n",
+            self.dir_color,
+            format_bytes(node.size)
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        let mut counts = (0, 0);
+        self.output_tree_level(node, "", 1, depth, &mut counts, &mut writer)?;
+
+        let output = format!("\n{} directories, {} files\n", counts.0, counts.1);
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
+
+    fn output_tree_level(
+        &self,
+        node: &Node,
+        prefix: &str,
+        level: usize,
+        depth: Option<usize>,
+        counts: &mut (usize, usize),
+        writer: &mut dyn std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let count = node.children.len();
+
+        for (i, child) in node.children.values().enumerate() {
+            let last = i + 1 == count;
+            let branch = if last { "└── " } else { "├── " };
+
+            let output = match child.is_directory() {
+                true => {
+                    counts.0 += 1;
+                    format!(
+                        "{prefix}{branch}{}{}/\x1b[0m\x1b[90m ({})\x1b[0m\n",
+                        self.dir_color,
+                        child.name,
+                        format_bytes(child.size)
+                    )
+                }
+                false => {
+                    counts.1 += 1;
+                    format!(
+                        "{prefix}{branch}{}{}\x1b[0m\x1b[90m ({})\x1b[0m\n",
+                        self.file_color,
+                        child.name,
+                        format_bytes(child.size)
+                    )
+                }
+            };
+            self.write(output.as_str(), &mut *writer)?;
+
+            let within_depth = match depth {
+                Some(d) => level < d,
+                None => true,
+            };
+            if child.is_directory() && within_depth {
+                let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                self.output_tree_level(child, &prefix, level + 1, depth, counts, writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn parse_response(
         &self,
         lr: ListResponse,
@@ -93,13 +209,7 @@ impl List {
     ) -> Result<(), NeocitiesErr> {
         let file_size: String;
         if let Some(n) = size {
-            if n < 1000 {
-                file_size = format!("{} B", n);
-            } else if n < 1000000 {
-                file_size = format!("{:.2} KB", n as f64 / 1000.0);
-            } else {
-                file_size = format!("{:.2} KB", n as f64 / 1000000.0);
-            }
+            file_size = format_bytes(u64::try_from(n)?);
         } else {
             file_size = String::from("0");
         }
@@ -151,8 +261,22 @@ impl Executable for List {
             return Ok(());
         }
 
+        let mut args = args;
+        let tree_depth = self.take_tree_opts(&mut args)?;
         let (is_detailed, path) = self.parse_args(args);
 
+        if let Some(depth) = tree_depth {
+            // a listing of one directory is flat, so the whole website is listed and the tree is
+            // drawn from the directory
+            let root = path.unwrap_or_default();
+            let tree = RemoteTree::from_list(&NcList::fetch(None)?);
+
+            return match tree.get(&root) {
+                Some(node) => self.output_tree(node, depth, &mut stdout),
+                None => self.write("No files were found\n", &mut stdout),
+            };
+        }
+
         let data = NcList::fetch(path)?;
 
         self.parse_response(data, is_detailed, &mut stdout)?;
@@ -173,14 +297,20 @@ impl Executable for List {
     }
 }
 
-const DESC: &'static str = "List files in your Neocities website";
+const DESC: &'static str = "List files in your Neocities website. Use `-a` for every file, \
+`-d <path>` for sizes and dates, and `--tree` to draw the files as a tree with the total size of \
+each directory, to the depth given by `-L <n>` or `--depth <n>`.";
 
 const DESC_SHORT: &'static str = "List files on Neocities";
 
 #[cfg(test)]
 mod tests {
     use super::{List, DESC, DESC_SHORT, KEY};
-    use crate::{api::list, client::command::Executable, error::NeocitiesErr};
+    use crate::{
+        api::list,
+        client::{command::Executable, tree::RemoteTree},
+        error::NeocitiesErr,
+    };
 
     #[test]
     fn list_write() -> Result<(), NeocitiesErr> {
@@ -294,4 +424,108 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn output_detailed_method() -> Result<(), NeocitiesErr> {
+        let l = List::new();
+        let date = String::from("bar");
+
+        let mut output = Vec::new();
+        l.output_detailed(
+            &String::from("foo"),
+            false,
+            Some(1_500_000),
+            &date,
+            &mut output,
+        )?;
+        assert_eq!(String::from_utf8(output)?.contains("(1.4 MiB)"), true);
+
+        let mut output = Vec::new();
+        l.output_detailed(&String::from("foo"), false, Some(2_048), &date, &mut output)?;
+        assert_eq!(String::from_utf8(output)?.contains("(2.0 KiB)"), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn take_tree_opts_method() -> Result<(), NeocitiesErr> {
+        let l = List::new();
+        let to_args = |s: &str| -> Vec<String> { s.split(' ').map(|x| x.to_string()).collect() };
+
+        let mut args = to_args("--tree /images");
+        assert_eq!(l.take_tree_opts(&mut args)?, Some(None));
+        assert_eq!(args, to_args("/images"));
+
+        let mut args = to_args("-a --depth 2");
+        assert_eq!(l.take_tree_opts(&mut args)?, Some(Some(2)));
+        assert_eq!(args, to_args("-a"));
+
+        // `-L` is the short form of `--depth`, as in `du`, since `-d` asks for details
+        let mut args = to_args("-L 1 /images");
+        assert_eq!(l.take_tree_opts(&mut args)?, Some(Some(1)));
+        assert_eq!(args, to_args("/images"));
+
+        let mut args = to_args("-d /images");
+        assert_eq!(l.take_tree_opts(&mut args)?, None);
+        assert_eq!(args.len(), 2);
+
+        assert_eq!(l.take_tree_opts(&mut to_args("--depth 0")).is_err(), true);
+        assert_eq!(
+            l.take_tree_opts(&mut to_args("--tree --depth")).is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_tree_method() -> Result<(), NeocitiesErr> {
+        let file = |path: &str, size: Option<i64>| list::File {
+            path: String::from(path),
+            is_directory: size.is_none(),
+            size,
+            updated_at: String::from("bar"),
+            sha1_hash: None,
+        };
+        let res = list::ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("index.html", Some(100)),
+                file("images", None),
+                file("images/a.png", Some(2048)),
+                file("images/icons", None),
+                file("images/icons/b.png", Some(1024)),
+            ],
+        };
+        let tree = RemoteTree::from_list(&res);
+        let l = List {
+            dir_color: "",
+            file_color: "",
+            ..List::new()
+        };
+
+        let mut output = Vec::new();
+        l.output_tree(tree.root(), None, &mut output)?;
+        let s = String::from_utf8(output)?
+            .replace("\x1b[0m", "")
+            .replace("\x1b[90m", "");
+        assert_eq!(
+            s,
+            ". (3.1 KiB)\n\
+             ├── images/ (3.0 KiB)\n\
+             │   ├── a.png (2.0 KiB)\n\
+             │   └── icons/ (1.0 KiB)\n\
+             │       └── b.png (1.0 KiB)\n\
+             └── index.html (100 B)\n\
+             \n2 directories, 3 files\n"
+        );
+
+        let mut output = Vec::new();
+        l.output_tree(tree.get("images").unwrap(), Some(1), &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("b.png"), false);
+        assert_eq!(s.contains("1 directories, 1 files"), true);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,398 @@
+use super::{
+    command::Executable,
+    journal::{JournalEntry, JournalFile},
+    output::Format,
+};
+use crate::error::NeocitiesErr;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use glob::{MatchOptions, Pattern};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "log";
+
+/// Shows the history of uploads and deletes recorded in the local journal.
+pub struct Log {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// Options that select and format journal entries
+#[derive(Debug, PartialEq)]
+struct LogOpts {
+    /// Only show entries that changed a matching path
+    path: Option<String>,
+    /// Only show entries made at or after this time
+    since: Option<DateTime<Utc>>,
+    /// Only show entries made at or before this time
+    until: Option<DateTime<Utc>>,
+    /// Show at most this many entries
+    limit: Option<usize>,
+    /// The format in which the entries are written to stdout
+    format: Format,
+}
+
+impl Log {
+    /// A constructor that returns an instance of `Log`.
+    pub fn new() -> Log {
+        Log {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m [--path <path|glob>] [--since <date>] [--until <date>] [-n <count>] [--output <text|porcelain|json>]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<LogOpts, NeocitiesErr> {
+        let mut opts = LogOpts {
+            path: None,
+            since: None,
+            until: None,
+            limit: None,
+            format: Format::Text,
+        };
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                opts.format = format;
+                i += consumed;
+                continue;
+            }
+
+            let value = match args.get(i + 1) {
+                Some(v) => v.as_str(),
+                None => return Err(NeocitiesErr::InvalidArgument),
+            };
+
+            match args[i].as_str() {
+                "--path" => opts.path = Some(value.trim_matches('/').to_string()),
+                "--since" => opts.since = Some(parse_date(value, false)?),
+                "--until" => opts.until = Some(parse_date(value, true)?),
+                "-n" | "--limit" => match value.parse::<usize>() {
+                    Ok(n) => opts.limit = Some(n),
+                    Err(_) => return Err(NeocitiesErr::InvalidArgument),
+                },
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 2;
+        }
+
+        Ok(opts)
+    }
+
+    /// Returns the entries that match the options, newest first.
+    fn select(
+        &self,
+        entries: Vec<JournalEntry>,
+        opts: &LogOpts,
+    ) -> Result<Vec<JournalEntry>, NeocitiesErr> {
+        let pattern = match &opts.path {
+            Some(p) => match Pattern::new(p) {
+                Ok(pattern) => Some(pattern),
+                Err(_) => return Err(NeocitiesErr::InvalidArgument),
+            },
+            None => None,
+        };
+
+        let mut selected: Vec<JournalEntry> = Vec::new();
+
+        for entry in entries.into_iter().rev() {
+            let time = DateTime::parse_from_rfc3339(&entry.timestamp)?;
+            if opts.since.is_some_and(|since| time < since) {
+                continue;
+            }
+            if opts.until.is_some_and(|until| time > until) {
+                continue;
+            }
+
+            if let (Some(path), Some(pattern)) = (&opts.path, &pattern) {
+                if !entry.files.iter().any(|f| matches_path(f, path, pattern)) {
+                    continue;
+                }
+            }
+
+            selected.push(entry);
+
+            if opts.limit.is_some_and(|n| selected.len() >= n) {
+                break;
+            }
+        }
+
+        Ok(selected)
+    }
+
+    fn write_text(
+        &self,
+        entries: &[JournalEntry],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        if entries.is_empty() {
+            self.write("No entries.\n", &mut writer)?;
+            return Ok(());
+        }
+
+        for entry in entries.iter() {
+            let mut output = format!(
+                "\x1b[1;93m#{}\x1b[0m {} ({}) {}\n",
+                entry.id, entry.operation, entry.command, entry.timestamp
+            );
+            if let Some(site) = &entry.site {
+                output.push_str(format!("  Site: {site}\n").as_str());
+            }
+            if let Some(user) = &entry.user {
+                output.push_str(format!("  User: {user}\n").as_str());
+            }
+            if let Some(head) = &entry.git_head {
+                output.push_str(format!("  Commit: {head}\n").as_str());
+            }
+            output.push_str(format!("  Status: {} - {}\n", entry.result, entry.message).as_str());
+            self.write(output.as_str(), &mut writer)?;
+
+            for file in entry.files.iter() {
+                let sha1 = file.sha1_hash.as_deref().unwrap_or("-");
+                let output = format!(
+                    "    {} {:>10} \x1b[92m{}\x1b[0m\n",
+                    &sha1[..sha1.len().min(7)],
+                    file.size.map(|s| format!("{s} B")).unwrap_or_default(),
+                    file.path
+                );
+                self.write(output.as_str(), &mut writer)?;
+            }
+
+            self.write("\n", &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one line per changed file: the entry id, the operation, the timestamp and the path,
+    /// separated by tabs.
+    fn write_porcelain(
+        &self,
+        entries: &[JournalEntry],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for entry in entries.iter() {
+            for file in entry.files.iter() {
+                let output = format!(
+                    "{}\t{}\t{}\t{}\n",
+                    entry.id, entry.operation, entry.timestamp, file.path
+                );
+                self.write(output.as_str(), &mut writer)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a date given on the command line, either as an RFC 3339 timestamp or as a day in the
+/// form `YYYY-MM-DD`. A day starts at midnight UTC, or ends just before the next midnight when
+/// `end_of_day` is set.
+pub fn parse_date(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, NeocitiesErr> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+    let time = match end_of_day {
+        true => NaiveTime::from_hms_milli_opt(23, 59, 59, 999),
+        false => NaiveTime::from_hms_opt(0, 0, 0),
+    };
+
+    match time {
+        Some(t) => Ok(Utc.from_utc_datetime(&day.and_time(t))),
+        None => Err(NeocitiesErr::InvalidArgument),
+    }
+}
+
+/// Returns true if a file is at the given path, inside it, or matches it as a glob.
+fn matches_path(file: &JournalFile, path: &str, pattern: &Pattern) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    file.path == path
+        || file.path.starts_with(format!("{path}/").as_str())
+        || pattern.matches_with(&file.path, options)
+}
+
+impl Default for Log {
+    fn default() -> Log {
+        Log::new()
+    }
+}
+
+impl Executable for Log {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let opts = self.parse_opts(args)?;
+        let entries = self.select(JournalEntry::read_all()?, &opts)?;
+
+        match opts.format {
+            Format::Json => {
+                let output = format!("{}\n", serde_json::to_string_pretty(&entries)?);
+                self.write(output.as_str(), &mut stdout)?;
+            }
+            Format::Porcelain => self.write_porcelain(&entries, &mut stdout)?,
+            Format::Text => self.write_text(&entries, &mut stdout)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Show the history of uploads and deletes made from this directory, newest \
+first. Every upload and delete is recorded in an append-only journal (.neocities/journal.jsonl, or \
+in the directory named by NEOCITIES_STATE_DIR) with its files, their SHA-1 hashes and sizes, the \
+site, the local user, the git commit checked out, and the server's response. Filter by `--path` \
+(a path, directory or glob), `--since` and `--until` (YYYY-MM-DD or an RFC 3339 timestamp), limit \
+the number of entries with `-n`, and use `--output json` for JSON.";
+
+const DESC_SHORT: &str = "Show deployment history";
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_date, Log, DESC, DESC_SHORT, KEY};
+    use crate::{
+        client::{
+            command::Executable,
+            journal::{JournalEntry, JournalFile},
+            output::Format,
+        },
+        error::NeocitiesErr,
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let l = Log::new();
+        assert_eq!(l.get_usage().contains(KEY), true);
+        assert_eq!(l.get_short_desc(), DESC_SHORT);
+        assert_eq!(l.get_long_desc(), DESC);
+    }
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let l = Log::new();
+
+        let opts = l.parse_opts(to_args("--path /images/ --since 2024-01-02 -n 5 --json"))?;
+        assert_eq!(opts.path.as_deref(), Some("images"));
+        assert_eq!(opts.since, Some(parse_date("2024-01-02T00:00:00Z", false)?));
+        assert_eq!(opts.limit, Some(5));
+        assert_eq!(opts.format, Format::Json);
+
+        assert_eq!(l.parse_opts(to_args("--since")).is_err(), true);
+        assert_eq!(l.parse_opts(to_args("--since yesterday")).is_err(), true);
+        assert_eq!(l.parse_opts(to_args("-n many")).is_err(), true);
+        assert_eq!(l.parse_opts(to_args("--bogus 1")).is_err(), true);
+
+        Ok(())
+    }
+
+    fn entry(id: u64, operation: &str, timestamp: &str, paths: &[&str]) -> JournalEntry {
+        JournalEntry {
+            id,
+            operation: operation.to_string(),
+            command: operation.to_string(),
+            timestamp: timestamp.to_string(),
+            site: Some(String::from("foo")),
+            user: None,
+            git_head: None,
+            files: paths
+                .iter()
+                .map(|p| JournalFile {
+                    path: p.to_string(),
+                    size: Some(1),
+                    sha1_hash: Some(String::from("0123456789abcdef")),
+                })
+                .collect(),
+            result: String::from("success"),
+            error_type: None,
+            message: String::from("ok"),
+        }
+    }
+
+    fn ids(entries: &[JournalEntry]) -> Vec<u64> {
+        entries.iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn select_method() -> Result<(), NeocitiesErr> {
+        let l = Log::new();
+        let entries = vec![
+            entry(1, "upload", "2024-01-01T10:00:00+00:00", &["index.html"]),
+            entry(2, "upload", "2024-01-02T10:00:00+00:00", &["images/a.png"]),
+            entry(3, "delete", "2024-01-03T10:00:00+00:00", &["images/b.png"]),
+            entry(4, "upload", "2024-01-04T10:00:00+00:00", &["about.html"]),
+        ];
+
+        let all = l.select(entries.clone(), &l.parse_opts(vec![])?)?;
+        assert_eq!(ids(&all), vec![4, 3, 2, 1]);
+
+        let opts = l.parse_opts(to_args("--path images"))?;
+        assert_eq!(ids(&l.select(entries.clone(), &opts)?), vec![3, 2]);
+
+        let opts = l.parse_opts(to_args("--path *.html"))?;
+        assert_eq!(ids(&l.select(entries.clone(), &opts)?), vec![4, 1]);
+
+        let opts = l.parse_opts(to_args("--since 2024-01-02 --until 2024-01-03"))?;
+        assert_eq!(ids(&l.select(entries.clone(), &opts)?), vec![3, 2]);
+
+        let opts = l.parse_opts(to_args("-n 1"))?;
+        assert_eq!(ids(&l.select(entries, &opts)?), vec![4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_methods() -> Result<(), NeocitiesErr> {
+        let l = Log::new();
+        let entries = vec![entry(
+            7,
+            "upload",
+            "2024-01-01T10:00:00+00:00",
+            &["index.html"],
+        )];
+
+        let mut output = Vec::new();
+        l.write_text(&entries, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("#7"), true);
+        assert_eq!(s.contains("0123456"), true);
+        assert_eq!(s.contains("index.html"), true);
+
+        let mut output = Vec::new();
+        l.write_porcelain(&entries, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "7\tupload\t2024-01-01T10:00:00+00:00\tindex.html\n"
+        );
+
+        Ok(())
+    }
+}
@@ -35,3 +35,83 @@ pub mod key;
 /// An implementation of `Executable` that diffs the state of a local project directory with its
 /// corresponding remote directory.
 pub mod diff;
+
+/// Parses and represents the output formats supported by commands that can be scripted, and the
+/// JSON report written by commands that change a website
+pub mod output;
+
+/// Implements the `Executable` trait and renames, or moves, files on a Neocities user's website
+pub mod mv;
+
+/// Implements the `Executable` trait and writes a plan of the uploads and deletes needed to make a
+/// Neocities user's website match a local directory
+pub mod plan;
+
+/// Implements the `Executable` trait and carries out a plan written by the `plan` command
+pub mod apply;
+
+/// Locates the directory where this application keeps local state, such as the trash, the journal
+/// and the object store
+pub mod state;
+
+/// Implements the `Executable` trait and lists and restores files saved to the local trash when they
+/// were deleted from a Neocities user's website
+pub mod trash;
+
+/// Records every upload and delete in an append-only journal kept in the state directory
+pub mod journal;
+
+/// Implements the `Executable` trait and shows the history of uploads and deletes recorded in the
+/// journal
+pub mod log;
+
+/// Keeps a content-addressed store of the files uploaded to a Neocities user's website, keyed by
+/// their SHA-1 hashes
+pub mod objects;
+
+/// Implements the `Executable` trait and returns a Neocities user's website to a deployment recorded
+/// in the journal
+pub mod rollback;
+
+/// Follows the bytes sent during an upload, and shows them as a bar in a terminal or as lines of
+/// JSON
+pub mod progress;
+
+/// Checks local files against the file types Neocities accepts, before they are uploaded
+pub mod filetype;
+
+/// Implements the `Executable` trait and shows how much of the account's storage a Neocities
+/// website uses, and computes the storage limits that uploads are checked against
+pub mod usage;
+
+/// Arranges the flat listing of a Neocities website as a tree, for drawing and lookups
+pub mod tree;
+
+/// Implements the `Executable` trait and searches the files of a Neocities user's website by name, type,
+/// size and modification date
+pub mod find;
+
+/// Shows the total size of the files under each directory of a website
+pub mod du;
+
+/// Shows the size, hash, modification time and URL of files on a website
+pub mod stat;
+
+/// Prints the live contents of files on a website
+pub mod cat;
+
+/// Opens a file on a website in a local editor and uploads it when saved
+pub mod edit;
+
+/// Deploys a local directory to a website whenever something in it changes
+pub mod watch;
+
+/// Serves a local directory the way Neocities would serve it once uploaded
+pub mod serve;
+
+/// Scaffolds a Neocities project: its config, ignore file and starter pages
+pub mod init;
+
+/// Reads the `.neocitiesignore` of a project, which leaves local paths out of uploads and
+/// comparisons
+pub mod ignore;
@@ -0,0 +1,332 @@
+use super::command::Executable;
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::{ListResponse, NcList},
+        rename::{NcRename, RenameResponse},
+    },
+    error::NeocitiesErr,
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "mv";
+
+/// Renames, or moves, a file or directory on a Neocities user's site
+pub struct Move {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+impl Move {
+    /// A constructor that returns an instance of `Move`.
+    pub fn new() -> Move {
+        Move {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m <from> <to>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_args(&self, args: Vec<String>) -> Result<(String, String), NeocitiesErr> {
+        if args.len() != 2 {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
+
+        let from = args[0].trim_matches('/').to_string();
+        let to = args[1].trim_matches('/').to_string();
+
+        if from.is_empty() || to.is_empty() || from == to {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
+
+        Ok((from, to))
+    }
+
+    /// Validates a move against the remote listing and returns the path that must be renamed,
+    /// as a pair of current and new paths. A directory is renamed as a whole, with everything in
+    /// it, so that no empty directory is left behind. If `to` is an existing directory, `from` is
+    /// moved into it.
+    fn resolve(
+        &self,
+        lr: &ListResponse,
+        from: &str,
+        to: &str,
+    ) -> Result<Vec<(String, String)>, NeocitiesErr> {
+        let source = match lr.files.iter().find(|f| f.path == from) {
+            Some(f) => f,
+            None => return Err(NeocitiesErr::MissingFile),
+        };
+
+        let mut dest = to.to_string();
+        if let Some(d) = lr.files.iter().find(|f| f.path == to) {
+            if !d.is_directory {
+                return Err(NeocitiesErr::FileExists);
+            }
+
+            let name = from.rsplit('/').next().unwrap_or(from);
+            dest = format!("{to}/{name}");
+        }
+
+        if source.is_directory && (dest == from || dest.starts_with(&format!("{from}/"))) {
+            return Err(NeocitiesErr::InvalidPath);
+        }
+
+        if lr.files.iter().any(|f| f.path == dest) {
+            return Err(NeocitiesErr::FileExists);
+        }
+
+        Ok(vec![(from.to_string(), dest)])
+    }
+
+    /// Renames each file with `rename`, writing the status of each, and carries on past a file
+    /// that fails. Returns the paths of the files that could not be moved.
+    fn rename_all(
+        &self,
+        moves: &[(String, String)],
+        rename: impl Fn(&str, &str) -> Result<RenameResponse, NeocitiesErr>,
+        mut writer: impl std::io::Write,
+    ) -> Result<Vec<String>, NeocitiesErr> {
+        let mut failed: Vec<String> = Vec::new();
+
+        for (path, new_path) in moves.iter() {
+            let output = match rename(path, new_path) {
+                Ok(data) => {
+                    if data.result != "success" {
+                        failed.push(path.clone());
+                    }
+                    format!(
+                        "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                        data.result, data.message
+                    )
+                }
+                Err(e) => {
+                    failed.push(path.clone());
+                    format!("\x1b[93mStatus\x1b[0m: error - {path}: {e}\n")
+                }
+            };
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(failed)
+    }
+}
+
+impl Default for Move {
+    fn default() -> Move {
+        Move::new()
+    }
+}
+
+impl Executable for Move {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        if args.is_empty() {
+            let output = format!("{}\nusage: {}\n", self.get_long_desc(), self.get_usage());
+            self.write(output.as_str(), &mut stdout)?;
+            return Ok(());
+        }
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let (from, to) = self.parse_args(args)?;
+
+        let list = NcList::fetch(None)?;
+        let moves = self.resolve(&list, &from, &to)?;
+
+        let failed = self.rename_all(&moves, NcRename::fetch, &mut stdout)?;
+        if !failed.is_empty() {
+            let output = format!(
+                "\x1b[91mNot moved\x1b[0m ({} of {}): {}\n",
+                failed.len(),
+                moves.len(),
+                failed.join(", ")
+            );
+            self.write(output.as_str(), &mut stdout)?;
+            return Err(NeocitiesErr::MoveFailed);
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Rename or move a file or directory on your Neocities website. A directory is \
+moved as a whole, with everything in it. If the move fails, the command exits with an error.";
+
+const DESC_SHORT: &str = "Move files on Neocities";
+
+#[cfg(test)]
+mod tests {
+    use super::{Move, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::{
+            list::{File, ListResponse},
+            rename::RenameResponse,
+        },
+        client::command::Executable,
+        error::NeocitiesErr,
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let m = Move::new();
+        assert_eq!(m.get_usage().contains(KEY), true);
+        assert_eq!(m.get_short_desc(), DESC_SHORT);
+        assert_eq!(m.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn parse_args_method() {
+        let m = Move::new();
+
+        let (from, to) = m
+            .parse_args(vec!["/img/".to_string(), "images".to_string()])
+            .unwrap();
+        assert_eq!(from, "img");
+        assert_eq!(to, "images");
+
+        assert_eq!(m.parse_args(vec!["foo".to_string()]).is_err(), true);
+        assert_eq!(
+            m.parse_args(vec!["foo".to_string(), "/foo".to_string()])
+                .is_err(),
+            true
+        );
+    }
+
+    fn mock_list() -> ListResponse {
+        let file = |path: &str, is_directory: bool| File {
+            path: String::from(path),
+            is_directory,
+            size: if is_directory { None } else { Some(1) },
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: None,
+        };
+
+        ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("index.html", false),
+                file("about.html", false),
+                file("img", true),
+                file("img/cat.png", false),
+                file("img/icons", true),
+                file("img/icons/dog.png", false),
+                file("css", true),
+            ],
+        }
+    }
+
+    #[test]
+    fn resolve_file() -> Result<(), NeocitiesErr> {
+        let m = Move::new();
+        let lr = mock_list();
+
+        let moves = m.resolve(&lr, "about.html", "me.html")?;
+        assert_eq!(
+            moves,
+            vec![(String::from("about.html"), String::from("me.html"))]
+        );
+
+        // moving into an existing directory keeps the file name
+        let moves = m.resolve(&lr, "about.html", "css")?;
+        assert_eq!(moves[0].1, "css/about.html");
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_directory() -> Result<(), NeocitiesErr> {
+        let m = Move::new();
+        let lr = mock_list();
+
+        // the directory is renamed with a single request, rather than file by file, so that the
+        // old directory does not stay behind empty
+        let moves = m.resolve(&lr, "img", "images")?;
+        assert_eq!(moves, vec![(String::from("img"), String::from("images"))]);
+
+        let moves = m.resolve(&lr, "img/icons", "css")?;
+        assert_eq!(
+            moves,
+            vec![(String::from("img/icons"), String::from("css/icons"))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_invalid() {
+        let m = Move::new();
+        let lr = mock_list();
+
+        assert_eq!(
+            m.resolve(&lr, "missing.html", "foo.html")
+                .unwrap_err()
+                .to_string(),
+            NeocitiesErr::MissingFile.to_string()
+        );
+        assert_eq!(
+            m.resolve(&lr, "about.html", "index.html")
+                .unwrap_err()
+                .to_string(),
+            NeocitiesErr::FileExists.to_string()
+        );
+        assert_eq!(m.resolve(&lr, "img", "img/icons").is_err(), true);
+    }
+
+    #[test]
+    fn rename_all_method() -> Result<(), NeocitiesErr> {
+        let m = Move::new();
+        let moves = vec![
+            (String::from("a.html"), String::from("x/a.html")),
+            (String::from("b.html"), String::from("x/b.html")),
+            (String::from("c.html"), String::from("x/c.html")),
+        ];
+
+        // a file refused by the server, or lost to a failed request, does not stop the others
+        let rename = |path: &str, _: &str| match path {
+            "a.html" => Ok(RenameResponse {
+                result: String::from("error"),
+                error_type: Some(String::from("missing_file")),
+                message: String::from("a.html was not found"),
+            }),
+            "b.html" => Err(NeocitiesErr::InvalidArgument),
+            _ => Ok(RenameResponse {
+                result: String::from("success"),
+                error_type: None,
+                message: String::from("c.html renamed to x/c.html"),
+            }),
+        };
+
+        let mut output = Vec::new();
+        let failed = m.rename_all(&moves, rename, &mut output)?;
+        assert_eq!(failed, vec!["a.html", "b.html"]);
+
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("error - a.html was not found"), true);
+        assert_eq!(s.contains("error - b.html: invalid argument"), true);
+        assert_eq!(s.contains("success - c.html renamed to x/c.html"), true);
+
+        Ok(())
+    }
+}
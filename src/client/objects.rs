@@ -0,0 +1,101 @@
+use super::{diff::Diff, journal::JournalFile, state};
+use crate::error::NeocitiesErr;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the directory, inside the state directory, where objects are kept
+pub const OBJECTS_DIR: &str = "objects";
+
+/// Returns the directory where objects are kept.
+pub fn dir() -> PathBuf {
+    state::dir().join(OBJECTS_DIR)
+}
+
+/// Returns the location of the object with the given SHA-1 hash. Like git, objects are spread
+/// across directories named after the first two characters of their hash.
+pub fn object_path(sha1_hash: &str) -> PathBuf {
+    let split = sha1_hash.len().min(2);
+    dir().join(&sha1_hash[..split]).join(&sha1_hash[split..])
+}
+
+/// Returns true if the object store holds the contents with the given SHA-1 hash.
+pub fn contains(sha1_hash: &str) -> bool {
+    object_path(sha1_hash).is_file()
+}
+
+/// Copies the contents of a local file into the object store, unless they are already there,
+/// and returns their SHA-1 hash.
+pub fn store(path: &Path) -> Result<String, NeocitiesErr> {
+    let sha1_hash = Diff::new().hash(&path.to_path_buf())?;
+
+    let object = object_path(&sha1_hash);
+    if !object.is_file() {
+        if let Some(parent) = object.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // copy under a temporary name first, so that an interrupted copy is never mistaken for a
+        // complete object
+        let partial = object.with_extension("partial");
+        fs::copy(path, &partial)?;
+        fs::rename(&partial, &object)?;
+    }
+
+    Ok(sha1_hash)
+}
+
+/// Stores a local file that is uploaded to the same path on the website, and describes it for
+/// the journal.
+pub fn snapshot(path: &str) -> Result<JournalFile, NeocitiesErr> {
+    let path_buf = PathBuf::from(path);
+
+    Ok(JournalFile {
+        path: Diff::new().format_path(&path_buf)?,
+        size: Some(i64::try_from(fs::metadata(&path_buf)?.len())?),
+        sha1_hash: Some(store(&path_buf)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contains, object_path, snapshot, store};
+    use crate::{client::state::TempStateDir, error::NeocitiesErr};
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    #[serial(env)]
+    fn store_and_snapshot() -> Result<(), NeocitiesErr> {
+        let state_dir = TempStateDir::new()?;
+        let temp_dir = tempfile::tempdir()?;
+
+        let local = temp_dir.path().join("index.html");
+        fs::write(&local, "Hello, World!")?;
+
+        let sha1_hash = store(&local)?;
+        assert_eq!(sha1_hash, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+        assert_eq!(contains(&sha1_hash), true);
+        assert_eq!(
+            object_path(&sha1_hash),
+            state_dir
+                .path()
+                .join("objects/0a/0a9f2a6772942557ab5355d76af442f8f65e01")
+        );
+        assert_eq!(
+            fs::read_to_string(object_path(&sha1_hash))?,
+            "Hello, World!"
+        );
+
+        // storing the same contents again is a no-op
+        assert_eq!(store(&local)?, sha1_hash);
+
+        let file = snapshot("tests/fixtures/foo.html")?;
+        assert_eq!(file.path, "tests/fixtures/foo.html");
+        assert_eq!(contains(file.sha1_hash.as_deref().unwrap()), true);
+        assert_eq!(contains("0000000000000000000000000000000000000000"), false);
+
+        Ok(())
+    }
+}
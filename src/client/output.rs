@@ -0,0 +1,238 @@
+use crate::error::NeocitiesErr;
+use serde_derive::Serialize;
+
+/// The formats in which a command can write its results to stdout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Human readable output, which may contain ANSI escape codes
+    Text,
+    /// A stable, line-oriented format intended for scripts
+    Porcelain,
+    /// A JSON document intended for scripts and other tools
+    Json,
+}
+
+impl Format {
+    /// Parses the value given to an `--output` flag
+    pub fn parse(value: &str) -> Result<Format, NeocitiesErr> {
+        match value {
+            "text" => Ok(Format::Text),
+            "porcelain" => Ok(Format::Porcelain),
+            "json" => Ok(Format::Json),
+            _ => Err(NeocitiesErr::InvalidArgument),
+        }
+    }
+
+    /// Checks whether an argument selects an output format. If so, it returns the format and the
+    /// number of arguments it consumes, so that both `--output json` and `--output=json` are
+    /// accepted, as well as the `--json` and `--porcelain` shorthands.
+    pub fn from_args(args: &[String]) -> Result<Option<(Format, usize)>, NeocitiesErr> {
+        let first = match args.first() {
+            Some(a) => a.as_str(),
+            None => return Ok(None),
+        };
+
+        match first {
+            "--json" => Ok(Some((Format::Json, 1))),
+            "--porcelain" => Ok(Some((Format::Porcelain, 1))),
+            "--output" | "-o" => match args.get(1) {
+                Some(v) => Ok(Some((Format::parse(v)?, 2))),
+                None => Err(NeocitiesErr::InvalidArgument),
+            },
+            _ => match first.strip_prefix("--output=") {
+                Some(v) => Ok(Some((Format::parse(v)?, 1))),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+/// A file affected by a command that changes a Neocities website
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReport {
+    /// The path of the file, as it appears on the remote
+    pub path: String,
+    /// The byte size of the file
+    pub size: Option<i64>,
+    /// The SHA-1 hash of the file
+    pub sha1_hash: Option<String>,
+    /// How the file relates to the remote listing, if it was checked (e.g. `new`, `changed`,
+    /// `unchanged` or `missing`), or what happened to it (e.g. `uploaded`, `skipped` or `failed`)
+    pub status: Option<String>,
+    /// Explains why the file failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// The JSON document written by commands that change a Neocities website, such as `upload` and
+/// `delete`. A dry run writes the same document, without a response from the server.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// The command that produced the report
+    pub command: String,
+    /// Indicates whether the request was only prepared, and not sent
+    pub dry_run: bool,
+    /// The files included in the request
+    pub files: Vec<FileReport>,
+    /// The total byte size of the files included in the request
+    pub total_bytes: i64,
+    /// The status message from the server
+    pub result: Option<String>,
+    /// The error type from the server
+    pub error_type: Option<String>,
+    /// The explanation from the server
+    pub message: Option<String>,
+}
+
+impl Report {
+    /// A constructor that returns a `Report` for the given files, without a server response.
+    pub fn new(command: &str, dry_run: bool, files: Vec<FileReport>) -> Report {
+        let total_bytes = files.iter().filter_map(|f| f.size).sum();
+
+        Report {
+            command: command.to_string(),
+            dry_run,
+            files,
+            total_bytes,
+            result: None,
+            error_type: None,
+            message: None,
+        }
+    }
+
+    /// Writes the report to a writer as a JSON document.
+    pub fn write_json(&self, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        let output = format!("{}\n", serde_json::to_string_pretty(self)?);
+        writer.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Formats a number of bytes with a binary unit, such as `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Parses a number of bytes, which may end with a binary unit of `K`, `M` or `G` (e.g. `500K` or
+/// `2M`), as given to `--limit-rate`.
+pub fn parse_bytes(value: &str) -> Result<u64, NeocitiesErr> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    match digits.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n * multiplier),
+        _ => Err(NeocitiesErr::InvalidArgument),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_bytes, parse_bytes, FileReport, Format, Report};
+    use crate::error::NeocitiesErr;
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split(' ').map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(Format::parse("text").unwrap(), Format::Text);
+        assert_eq!(Format::parse("porcelain").unwrap(), Format::Porcelain);
+        assert_eq!(Format::parse("json").unwrap(), Format::Json);
+        assert_eq!(Format::parse("yaml").is_err(), true);
+    }
+
+    #[test]
+    fn format_from_args() {
+        let (f, n) = Format::from_args(&to_args("--output json foo"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(f, Format::Json);
+        assert_eq!(n, 2);
+
+        let (f, n) = Format::from_args(&to_args("--output=porcelain foo"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(f, Format::Porcelain);
+        assert_eq!(n, 1);
+
+        let (f, n) = Format::from_args(&to_args("--json")).unwrap().unwrap();
+        assert_eq!(f, Format::Json);
+        assert_eq!(n, 1);
+
+        assert_eq!(Format::from_args(&to_args("foo")).unwrap().is_none(), true);
+        assert_eq!(Format::from_args(&to_args("--output")).is_err(), true);
+        assert_eq!(Format::from_args(&to_args("--output xml")).is_err(), true);
+    }
+
+    #[test]
+    fn report_json() {
+        let files = vec![
+            FileReport {
+                path: String::from("foo.html"),
+                size: Some(10),
+                sha1_hash: Some(String::from("bar")),
+                status: Some(String::from("new")),
+                message: None,
+            },
+            FileReport {
+                path: String::from("baz.html"),
+                size: Some(5),
+                sha1_hash: None,
+                status: None,
+                message: None,
+            },
+        ];
+
+        let report = Report::new("upload", true, files);
+        assert_eq!(report.total_bytes, 15);
+
+        let mut output = Vec::new();
+        report.write_json(&mut output).unwrap();
+
+        let v: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(v["command"], "upload");
+        assert_eq!(v["dry_run"], true);
+        assert_eq!(v["files"][0]["path"], "foo.html");
+        assert_eq!(v["total_bytes"], 15);
+        assert_eq!(v["result"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn format_bytes_function() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn parse_bytes_function() -> Result<(), NeocitiesErr> {
+        assert_eq!(parse_bytes("100")?, 100);
+        assert_eq!(parse_bytes("500K")?, 500 * 1024);
+        assert_eq!(parse_bytes("2m")?, 2 * 1024 * 1024);
+        assert_eq!(parse_bytes("1G")?, 1024 * 1024 * 1024);
+        assert_eq!(parse_bytes("0").is_err(), true);
+        assert_eq!(parse_bytes("fast").is_err(), true);
+        assert_eq!(parse_bytes("K").is_err(), true);
+        Ok(())
+    }
+}
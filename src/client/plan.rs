@@ -0,0 +1,405 @@
+use super::{
+    command::Executable,
+    diff::{Diff, Item, Status},
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::{ListResponse, NcList},
+    },
+    error::NeocitiesErr,
+};
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "plan";
+
+/// The file a plan is written to when no other file is specified
+pub const DEFAULT_PLAN_FILE: &str = "neocities.plan.json";
+
+/// The version of the plan file format written by this client
+pub const PLAN_VERSION: u32 = 1;
+
+/// Computes the uploads and deletes needed to make a Neocities website match a local directory,
+/// and writes them to a plan file that can be reviewed and later carried out with `apply`.
+pub struct Plan {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// A file or directory recorded in a plan, along with its expected SHA-1 hash
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PlanEntry {
+    /// The path of the file, as it appears on the remote
+    pub path: String,
+    /// Indicates whether the entry is a directory
+    pub is_directory: bool,
+    /// The byte size of the file
+    pub size: Option<i64>,
+    /// The expected SHA-1 hash of the file
+    pub sha1_hash: Option<String>,
+}
+
+/// The contents of a plan file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanFile {
+    /// The version of the plan file format
+    pub version: u32,
+    /// The local directory the plan was computed for, which is also the remote path it covers
+    pub local_dir: String,
+    /// A timestamp for the plan's creation
+    pub created_at: String,
+    /// The remote files covered by the plan at the time it was created. The plan is only applied
+    /// if the remote still matches this baseline.
+    pub baseline: Vec<PlanEntry>,
+    /// The local files that will be uploaded, with the SHA-1 hashes they had when the plan was
+    /// created
+    pub uploads: Vec<PlanEntry>,
+    /// The remote files and directories that will be deleted
+    pub deletes: Vec<PlanEntry>,
+}
+
+impl PlanFile {
+    /// Computes a plan for a local directory, using the same comparison as the `diff` command.
+    pub fn build(local_dir: &Path, remote_list: ListResponse) -> Result<PlanFile, NeocitiesErr> {
+        let diff = Diff::new();
+
+        let baseline = PlanFile::baseline_of(local_dir, &remote_list)?;
+        let changes = diff.compare(local_dir.to_path_buf(), remote_list)?;
+
+        let mut uploads: Vec<PlanEntry> = Vec::new();
+        let mut deletes: Vec<PlanEntry> = Vec::new();
+
+        for change in changes.into_iter() {
+            match change.status {
+                Status::Added | Status::Modified | Status::Conflict | Status::Renamed => {
+                    // directories are created on the remote as files are uploaded into them
+                    if change.is_directory {
+                        continue;
+                    }
+
+                    uploads.push(PlanEntry {
+                        path: change.path,
+                        is_directory: false,
+                        size: change.local_size,
+                        sha1_hash: change.local_sha1,
+                    });
+                }
+                Status::Deleted => deletes.push(PlanEntry {
+                    path: change.path,
+                    is_directory: change.is_directory,
+                    size: None,
+                    sha1_hash: change.remote_sha1,
+                }),
+            }
+        }
+
+        Ok(PlanFile {
+            version: PLAN_VERSION,
+            local_dir: diff.format_path(&local_dir.to_path_buf())?,
+            created_at: Utc::now().to_rfc2822(),
+            baseline,
+            uploads,
+            deletes: PlanFile::prune_deletes(deletes),
+        })
+    }
+
+    /// Returns the remote files covered by a plan for a local directory, sorted by path.
+    pub fn baseline_of(
+        local_dir: &Path,
+        remote_list: &ListResponse,
+    ) -> Result<Vec<PlanEntry>, NeocitiesErr> {
+        let mut remote_map: HashMap<String, Item> = HashMap::new();
+        Diff::new().remote_items(
+            &mut remote_map,
+            local_dir.to_path_buf(),
+            remote_list.clone(),
+        )?;
+
+        let mut baseline: Vec<PlanEntry> = remote_map
+            .values()
+            .map(|item| PlanEntry {
+                path: item.file().path.clone(),
+                is_directory: item.file().is_directory,
+                size: item.file().size,
+                sha1_hash: item.file().sha1_hash.clone(),
+            })
+            .collect();
+
+        baseline.sort();
+        Ok(baseline)
+    }
+
+    /// Drops the deletes whose parent directory is deleted as well, since deleting a directory
+    /// deletes its contents.
+    fn prune_deletes(deletes: Vec<PlanEntry>) -> Vec<PlanEntry> {
+        let dirs: Vec<String> = deletes
+            .iter()
+            .filter(|d| d.is_directory)
+            .map(|d| format!("{}/", d.path))
+            .collect();
+
+        deletes
+            .into_iter()
+            .filter(|d| !dirs.iter().any(|dir| d.path.starts_with(dir.as_str())))
+            .collect()
+    }
+
+    /// Reads a plan from a file.
+    pub fn read(path: &Path) -> Result<PlanFile, NeocitiesErr> {
+        let contents = fs::read_to_string(path)?;
+        let plan: PlanFile = serde_json::from_str(&contents)?;
+        Ok(plan)
+    }
+
+    /// Writes the plan to a file as JSON.
+    pub fn write(&self, path: &Path) -> Result<(), NeocitiesErr> {
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, format!("{contents}\n"))?;
+        Ok(())
+    }
+}
+
+impl Plan {
+    /// A constructor that returns an instance of `Plan`.
+    pub fn new() -> Plan {
+        Plan {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m [--out <plan-file>] <local-dir>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_args(&self, args: Vec<String>) -> Result<(PathBuf, PathBuf), NeocitiesErr> {
+        let mut out = PathBuf::from(DEFAULT_PLAN_FILE);
+        let mut dir: Option<PathBuf> = None;
+
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-o" | "--out" => match iter.next() {
+                    Some(o) => out = PathBuf::from(o),
+                    None => return Err(NeocitiesErr::InvalidArgument),
+                },
+                _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+        }
+
+        let dir = match dir {
+            Some(d) if d.is_dir() => d,
+            Some(_) => return Err(NeocitiesErr::InvalidPath),
+            None => return Err(NeocitiesErr::InvalidArgument),
+        };
+
+        Ok((dir, out))
+    }
+
+    fn write_summary(
+        &self,
+        plan: &PlanFile,
+        out: &Path,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for entry in plan.uploads.iter() {
+            let output = format!("\x1b[1;92mupload\x1b[0m {}\n", entry.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        for entry in plan.deletes.iter() {
+            let output = format!("\x1b[1;91mdelete\x1b[0m {}\n", entry.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        let output = format!(
+            "Plan written to {}: {} upload(s), {} delete(s)\n",
+            out.display(),
+            plan.uploads.len(),
+            plan.deletes.len()
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
+}
+
+impl Default for Plan {
+    fn default() -> Plan {
+        Plan::new()
+    }
+}
+
+impl Executable for Plan {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        if args.is_empty() {
+            let output = format!("{}\nusage: {}\n", self.get_long_desc(), self.get_usage());
+            self.write(output.as_str(), &mut stdout)?;
+            return Ok(());
+        }
+
+        let (dir, out) = self.parse_args(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let list = NcList::fetch(None)?;
+        let plan = PlanFile::build(&dir, list)?;
+        plan.write(&out)?;
+
+        self.write_summary(&plan, &out, &mut stdout)?;
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Compute the uploads and deletes needed to make your Neocities website \
+match a local directory, and write them to a plan file (neocities.plan.json by default). Review the \
+plan, then carry it out with `apply`.";
+
+const DESC_SHORT: &str = "Plan a deployment";
+
+#[cfg(test)]
+mod tests {
+    use super::{Plan, PlanEntry, PlanFile, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{command::Executable, diff::Diff},
+        error::NeocitiesErr,
+    };
+    use std::fs;
+
+    #[test]
+    fn usage_desc_methods() {
+        let p = Plan::new();
+        assert_eq!(p.get_usage().contains(KEY), true);
+        assert_eq!(p.get_short_desc(), DESC_SHORT);
+        assert_eq!(p.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn parse_args_method() {
+        let p = Plan::new();
+
+        let (dir, out) = p
+            .parse_args(vec![
+                "tests/fixtures".to_string(),
+                "--out".to_string(),
+                "foo.json".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(dir.to_str().unwrap(), "tests/fixtures");
+        assert_eq!(out.to_str().unwrap(), "foo.json");
+
+        assert_eq!(p.parse_args(vec!["--out".to_string()]).is_err(), true);
+        assert_eq!(p.parse_args(vec!["nonexistent".to_string()]).is_err(), true);
+    }
+
+    fn remote_file(path: &str, is_directory: bool, sha1_hash: Option<&str>) -> File {
+        File {
+            path: path.to_string(),
+            is_directory,
+            size: sha1_hash.map(|_| 1),
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: sha1_hash.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn build_plan() -> Result<(), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        let site = temp_dir.path().join("site");
+        fs::create_dir(&site)?;
+        fs::write(site.join("index.html"), "Hello, World!")?;
+        fs::write(site.join("new.html"), "new")?;
+
+        let prefix = Diff::new().format_path(&site)?;
+        let remote = |p: &str| format!("{prefix}/{p}");
+
+        let list = ListResponse {
+            result: String::from("success"),
+            files: vec![
+                remote_file(&prefix, true, None),
+                remote_file(&remote("index.html"), false, Some("outdated")),
+                remote_file(&remote("old.html"), false, Some("old")),
+                remote_file(&remote("drafts"), true, None),
+                remote_file(&remote("drafts/a.html"), false, Some("a")),
+                remote_file("elsewhere.html", false, Some("x")),
+            ],
+        };
+
+        let plan = PlanFile::build(&site, list)?;
+
+        assert_eq!(plan.local_dir, prefix);
+
+        // only the remote files under the local directory make up the baseline
+        assert_eq!(plan.baseline.len(), 5);
+        assert!(plan.baseline.iter().all(|e| e.path.starts_with(&prefix)));
+
+        let uploads: Vec<&str> = plan.uploads.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(uploads, vec![remote("index.html"), remote("new.html")]);
+        assert_eq!(
+            plan.uploads[0].sha1_hash.as_deref(),
+            Some("0a0a9f2a6772942557ab5355d76af442f8f65e01")
+        );
+        assert_eq!(plan.uploads[0].size, Some(13));
+
+        // the contents of a deleted directory are not deleted separately
+        let deletes: Vec<&str> = plan.deletes.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(deletes, vec![remote("drafts"), remote("old.html")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_and_read_plan() -> Result<(), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("plan.json");
+
+        let plan = PlanFile {
+            version: 1,
+            local_dir: String::from("site"),
+            created_at: String::from("Sat, 13 Feb 2016 03:04:00 +0000"),
+            baseline: vec![],
+            uploads: vec![PlanEntry {
+                path: String::from("site/index.html"),
+                is_directory: false,
+                size: Some(1),
+                sha1_hash: Some(String::from("foo")),
+            }],
+            deletes: vec![],
+        };
+
+        plan.write(&path)?;
+        assert_eq!(PlanFile::read(&path)?, plan);
+
+        Ok(())
+    }
+}
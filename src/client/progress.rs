@@ -0,0 +1,205 @@
+use super::output::format_bytes;
+use crate::error::NeocitiesErr;
+use serde_derive::Serialize;
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The width, in characters, of the bar drawn in a terminal
+const BAR_WIDTH: usize = 30;
+
+/// The shortest time between two updates, so that a fast upload does not flood the terminal
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How the progress of an upload is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    /// A bar redrawn in place on stderr, for a terminal
+    Bar,
+    /// A line of JSON on stderr for each update, for scripts
+    Json,
+    /// Nothing is shown
+    Silent,
+}
+
+/// A line written for each update in the `Json` style
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressEvent {
+    /// Always `progress`
+    pub event: String,
+    /// The path of the file being sent
+    pub path: String,
+    /// The bytes of the file sent so far
+    pub sent: u64,
+    /// The byte size of the file
+    pub size: u64,
+    /// The bytes of every file sent so far
+    pub total_sent: u64,
+    /// The byte size of every file in the upload
+    pub total_size: u64,
+}
+
+/// Follows the bytes sent of each file in an upload, and shows them in the chosen style. It is
+/// updated from the requests as they stream, which may be several at once.
+pub struct Progress {
+    style: Style,
+    sizes: HashMap<String, u64>,
+    total_size: u64,
+    state: Mutex<State>,
+}
+
+struct State {
+    sent: HashMap<String, u64>,
+    last_update: Option<Instant>,
+}
+
+impl Progress {
+    /// Creates a tracker for the given files and their byte sizes.
+    pub fn new(style: Style, files: &[(String, u64)]) -> Progress {
+        Progress {
+            style,
+            sizes: files.iter().cloned().collect(),
+            total_size: files.iter().map(|(_, size)| size).sum(),
+            state: Mutex::new(State {
+                sent: HashMap::new(),
+                last_update: None,
+            }),
+        }
+    }
+
+    /// Records that `sent` bytes of the file at `path` have been sent in its current request, and
+    /// shows the progress unless it was shown very recently. A file that is sent again starts
+    /// over from zero.
+    pub fn update(&self, path: &str, sent: u64) {
+        let event = {
+            let mut state = match self.state.lock() {
+                Ok(s) => s,
+                Err(_) => return,
+            };
+            state.sent.insert(path.to_string(), sent);
+
+            let size = self.sizes.get(path).copied().unwrap_or_default();
+            let recent = state
+                .last_update
+                .is_some_and(|t| t.elapsed() < REDRAW_INTERVAL);
+            if recent && sent < size {
+                return;
+            }
+            state.last_update = Some(Instant::now());
+
+            self.event(&state, path, sent)
+        };
+
+        let output = match self.style {
+            Style::Bar => format!("\r\x1b[K{}", self.bar(&event)),
+            Style::Json => match serde_json::to_string(&event) {
+                Ok(line) => format!("{line}\n"),
+                Err(_) => return,
+            },
+            Style::Silent => return,
+        };
+
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(output.as_bytes());
+        let _ = stderr.flush();
+    }
+
+    /// Clears the bar from the terminal, once the upload is over.
+    pub fn finish(&self) -> Result<(), NeocitiesErr> {
+        if self.style == Style::Bar {
+            let mut stderr = std::io::stderr();
+            stderr.write_all(b"\r\x1b[K")?;
+            stderr.flush()?;
+        }
+        Ok(())
+    }
+
+    fn event(&self, state: &State, path: &str, sent: u64) -> ProgressEvent {
+        let total_sent = state
+            .sent
+            .iter()
+            .map(|(p, s)| match self.sizes.get(p) {
+                Some(size) => (*s).min(*size),
+                None => *s,
+            })
+            .sum();
+
+        ProgressEvent {
+            event: String::from("progress"),
+            path: path.to_string(),
+            sent,
+            size: self.sizes.get(path).copied().unwrap_or_default(),
+            total_sent,
+            total_size: self.total_size,
+        }
+    }
+
+    fn bar(&self, event: &ProgressEvent) -> String {
+        let ratio = match event.total_size {
+            0 => 1.0,
+            t => (event.total_sent as f64 / t as f64).min(1.0),
+        };
+        let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+
+        format!(
+            "[{}{}] {:>3}% {}/{} {}",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            (ratio * 100.0).round() as u64,
+            format_bytes(event.total_sent),
+            format_bytes(event.total_size),
+            event.path
+        )
+    }
+}
+
+/// Writes lines of output to stdout, clearing the bar from the terminal first so that the two do
+/// not run together. The bar is drawn again on the next update.
+impl Write for &Progress {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.style == Style::Bar {
+            std::io::stderr().write_all(b"\r\x1b[K")?;
+        }
+        std::io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Progress, Style};
+
+    #[test]
+    fn update_method() {
+        let files = vec![(String::from("a.html"), 100), (String::from("b.css"), 300)];
+        let p = Progress::new(Style::Silent, &files);
+
+        p.update("a.html", 100);
+        p.update("b.css", 100);
+
+        let state = p.state.lock().unwrap();
+        let event = p.event(&state, "b.css", 100);
+        assert_eq!(event.total_sent, 200);
+        assert_eq!(event.total_size, 400);
+        assert_eq!(event.size, 300);
+
+        let bar = p.bar(&event);
+        assert_eq!(
+            bar.starts_with(&format!("[{}{}]", "#".repeat(15), "-".repeat(15))),
+            true
+        );
+        assert_eq!(bar.contains(" 50% 200 B/400 B b.css"), true);
+        drop(state);
+
+        // a file sent again starts over
+        p.update("a.html", 10);
+        let state = p.state.lock().unwrap();
+        assert_eq!(p.event(&state, "a.html", 10).total_sent, 110);
+    }
+}
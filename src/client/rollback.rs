@@ -0,0 +1,557 @@
+use super::{
+    command::Executable,
+    journal::{JournalEntry, JournalFile},
+    objects, trash,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        delete::NcDelete,
+        list::{ListResponse, NcList},
+        upload::NcUpload,
+    },
+    error::NeocitiesErr,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, BufRead, IsTerminal},
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "rollback";
+
+/// Returns a Neocities website to the state it was in after a deployment recorded in the journal,
+/// re-uploading old contents from the object store and deleting files that were added since.
+pub struct Rollback {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// Options that alter how a rollback is carried out
+struct RollbackOpts {
+    /// Report the changes without making them
+    dry_run: bool,
+    /// Skip the confirmation prompt
+    yes: bool,
+}
+
+/// The changes that return the website to a recorded deployment
+#[derive(Debug, PartialEq)]
+struct Changes {
+    /// The files to upload from the object store, with the contents they had after the deployment
+    uploads: Vec<JournalFile>,
+    /// The files on the website that were added after the deployment
+    deletes: Vec<JournalFile>,
+}
+
+impl Rollback {
+    /// A constructor that returns an instance of `Rollback`.
+    pub fn new() -> Rollback {
+        Rollback {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m [--dry-run] [--yes] <deploy-id>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_args(&self, args: Vec<String>) -> Result<(RollbackOpts, u64), NeocitiesErr> {
+        let mut opts = RollbackOpts {
+            dry_run: false,
+            yes: false,
+        };
+        let mut id: Option<u64> = None;
+
+        for arg in args.iter() {
+            match arg.as_str() {
+                "-n" | "--dry-run" => opts.dry_run = true,
+                "-y" | "--yes" | "-f" | "--force" => opts.yes = true,
+                _ if id.is_none() => match arg.trim_start_matches('#').parse::<u64>() {
+                    Ok(n) => id = Some(n),
+                    Err(_) => return Err(NeocitiesErr::InvalidArgument),
+                },
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+        }
+
+        match id {
+            Some(id) => Ok((opts, id)),
+            None => Err(NeocitiesErr::InvalidArgument),
+        }
+    }
+
+    /// Keeps the journal entries recorded for the given site, so that a journal shared by several
+    /// websites only rolls back the one in the credentials.
+    fn site_entries(&self, entries: Vec<JournalEntry>, site: &str) -> Vec<JournalEntry> {
+        entries
+            .into_iter()
+            .filter(|e| e.site.as_deref() == Some(site))
+            .collect()
+    }
+
+    /// Replays the successful journal entries up to and including the given id, and returns the
+    /// files the website held afterwards, as far as the journal knows.
+    fn state_at(&self, entries: &[JournalEntry], id: u64) -> BTreeMap<String, JournalFile> {
+        let mut files: BTreeMap<String, JournalFile> = BTreeMap::new();
+
+        for entry in entries
+            .iter()
+            .filter(|e| e.id <= id && e.result == "success")
+        {
+            for file in entry.files.iter() {
+                match entry.operation.as_str() {
+                    "upload" => {
+                        files.insert(file.path.clone(), file.clone());
+                    }
+                    "delete" => {
+                        // deleting a directory deletes everything inside it
+                        let prefix = format!("{}/", file.path);
+                        files.retain(|path, _| *path != file.path && !path.starts_with(&prefix));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        files
+    }
+
+    /// Compares the remote listing with the state after a deployment. Files that differ or are
+    /// missing are uploaded again, and files uploaded by later entries are deleted. Files the
+    /// journal has never uploaded are left alone.
+    fn changes(
+        &self,
+        entries: &[JournalEntry],
+        id: u64,
+        remote: &ListResponse,
+    ) -> Result<Changes, NeocitiesErr> {
+        if !entries.iter().any(|e| e.id == id) {
+            return Err(NeocitiesErr::UnknownDeployId);
+        }
+
+        let target = self.state_at(entries, id);
+
+        let added_since: BTreeSet<&str> = entries
+            .iter()
+            .filter(|e| e.id > id && e.result == "success" && e.operation == "upload")
+            .flat_map(|e| e.files.iter().map(|f| f.path.as_str()))
+            .collect();
+
+        let mut uploads: Vec<JournalFile> = Vec::new();
+        for (path, file) in target.iter() {
+            let current = remote
+                .files
+                .iter()
+                .find(|r| r.path == *path && !r.is_directory);
+
+            if current.is_some_and(|r| r.sha1_hash == file.sha1_hash) {
+                continue;
+            }
+
+            match &file.sha1_hash {
+                Some(sha1_hash) if objects::contains(sha1_hash) => uploads.push(file.clone()),
+                _ => return Err(NeocitiesErr::MissingObject),
+            }
+        }
+
+        let deletes: Vec<JournalFile> = remote
+            .files
+            .iter()
+            .filter(|r| !r.is_directory)
+            .filter(|r| !target.contains_key(&r.path) && added_since.contains(r.path.as_str()))
+            .map(JournalFile::from)
+            .collect();
+
+        Ok(Changes { uploads, deletes })
+    }
+
+    fn write_changes(
+        &self,
+        changes: &Changes,
+        id: u64,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for file in changes.uploads.iter() {
+            let output = format!("\x1b[1;92mrestore\x1b[0m {}\n", file.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        for file in changes.deletes.iter() {
+            let output = format!("\x1b[1;91mdelete\x1b[0m  {}\n", file.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        let output = format!(
+            "Rollback to #{id}: {} restore(s), {} delete(s)\n",
+            changes.uploads.len(),
+            changes.deletes.len()
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
+
+    /// Asks the user to confirm the rollback. A closed stdin cancels it.
+    fn confirm(
+        &self,
+        mut reader: impl BufRead,
+        mut writer: impl std::io::Write,
+    ) -> Result<bool, NeocitiesErr> {
+        loop {
+            self.write("Continue with the rollback? [y/N] ", &mut writer)?;
+            writer.flush()?;
+
+            let mut input = String::new();
+            if reader.read_line(&mut input)? == 0 {
+                self.write("\nCanceling rollback.\n", &mut writer)?;
+                return Ok(false);
+            }
+
+            match input.trim() {
+                "Y" | "y" => return Ok(true),
+                "N" | "n" | "" => {
+                    self.write("Canceling rollback.\n", &mut writer)?;
+                    return Ok(false);
+                }
+                other => {
+                    let err = format!("Invalid input: '{other}'. Please try again.\n");
+                    self.write(err.as_str(), &mut writer)?;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Rollback {
+    fn default() -> Rollback {
+        Rollback::new()
+    }
+}
+
+impl Executable for Rollback {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        if args.is_empty() {
+            let output = format!("{}\nusage: {}\n", self.get_long_desc(), self.get_usage());
+            self.write(output.as_str(), &mut stdout)?;
+            return Ok(());
+        }
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let (opts, id) = self.parse_args(args)?;
+
+        let site = trash::sitename()?;
+        let entries = self.site_entries(JournalEntry::read_all()?, &site);
+        let list = NcList::fetch(None)?;
+        let changes = self.changes(&entries, id, &list)?;
+
+        self.write_changes(&changes, id, &mut stdout)?;
+
+        if opts.dry_run || (changes.uploads.is_empty() && changes.deletes.is_empty()) {
+            return Ok(());
+        }
+
+        if !opts.yes {
+            let stdin = io::stdin();
+            if !stdin.is_terminal() {
+                return Err(NeocitiesErr::NotInteractive);
+            }
+            if !self.confirm(stdin.lock(), &mut stdout)? {
+                return Ok(());
+            }
+        }
+
+        if !changes.uploads.is_empty() {
+            let mut files: Vec<(String, String)> = Vec::new();
+            for file in changes.uploads.iter() {
+                let object = objects::object_path(file.sha1_hash.as_deref().unwrap_or_default());
+                match object.to_str() {
+                    Some(o) => files.push((o.to_string(), file.path.clone())),
+                    None => return Err(NeocitiesErr::InvalidPath),
+                }
+            }
+
+            let data = NcUpload::fetch_as(files)?;
+            JournalEntry::new("upload", KEY, Some(site.clone()), changes.uploads)
+                .respond(&data.result, data.error_type.clone(), &data.message)
+                .append()?;
+            let output = format!(
+                "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                data.result, data.message
+            );
+            self.write(output.as_str(), &mut stdout)?;
+
+            // the files added since are only deleted once the old ones are back
+            if data.result != "success" {
+                return Err(NeocitiesErr::RequestFailed);
+            }
+        }
+
+        if !changes.deletes.is_empty() {
+            let paths: Vec<String> = changes.deletes.iter().map(|f| f.path.clone()).collect();
+            let data = NcDelete::fetch(paths)?;
+            JournalEntry::new("delete", KEY, Some(site), changes.deletes)
+                .respond(&data.result, data.error_type.clone(), &data.message)
+                .append()?;
+            let output = format!(
+                "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                data.result, data.message
+            );
+            self.write(output.as_str(), &mut stdout)?;
+
+            if data.result != "success" {
+                return Err(NeocitiesErr::RequestFailed);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Return your Neocities website to the state it was in after a deployment \
+recorded in the journal (see `log` for ids). Every upload keeps a copy of its files in a local \
+object store (.neocities/objects), keyed by SHA-1. A rollback uploads the old contents of files \
+that changed since, and deletes files that were uploaded after the deployment. Only the journal \
+entries of the site in your credentials are used, and files that were never uploaded from this \
+directory are left alone. Use `--dry-run` to see the changes without \
+making them, and `--yes` to skip the confirmation.";
+
+const DESC_SHORT: &str = "Roll back to a deployment";
+
+#[cfg(test)]
+mod tests {
+    use super::{Rollback, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{
+            command::Executable,
+            journal::{JournalEntry, JournalFile},
+            objects,
+            state::TempStateDir,
+        },
+        error::NeocitiesErr,
+    };
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    fn usage_desc_methods() {
+        let r = Rollback::new();
+        assert_eq!(r.get_usage().contains(KEY), true);
+        assert_eq!(r.get_short_desc(), DESC_SHORT);
+        assert_eq!(r.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn parse_args_method() -> Result<(), NeocitiesErr> {
+        let r = Rollback::new();
+
+        let (opts, id) = r.parse_args(vec!["--dry-run".to_string(), "#12".to_string()])?;
+        assert_eq!(opts.dry_run, true);
+        assert_eq!(opts.yes, false);
+        assert_eq!(id, 12);
+
+        assert_eq!(r.parse_args(vec!["--yes".to_string()]).is_err(), true);
+        assert_eq!(r.parse_args(vec!["latest".to_string()]).is_err(), true);
+        assert_eq!(
+            r.parse_args(vec!["1".to_string(), "2".to_string()])
+                .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    fn journal_file(path: &str, sha1_hash: &str) -> JournalFile {
+        JournalFile {
+            path: path.to_string(),
+            size: Some(1),
+            sha1_hash: Some(sha1_hash.to_string()),
+        }
+    }
+
+    fn entry(id: u64, operation: &str, files: Vec<JournalFile>) -> JournalEntry {
+        JournalEntry {
+            id,
+            operation: operation.to_string(),
+            command: operation.to_string(),
+            timestamp: String::from("2024-01-01T10:00:00+00:00"),
+            site: None,
+            user: None,
+            git_head: None,
+            files,
+            result: String::from("success"),
+            error_type: None,
+            message: String::new(),
+        }
+    }
+
+    fn remote_file(path: &str, sha1_hash: &str) -> File {
+        File {
+            path: path.to_string(),
+            is_directory: false,
+            size: Some(1),
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: Some(sha1_hash.to_string()),
+        }
+    }
+
+    fn paths(files: &[JournalFile]) -> Vec<&str> {
+        files.iter().map(|f| f.path.as_str()).collect()
+    }
+
+    #[test]
+    fn state_at_method() {
+        let r = Rollback::new();
+        let mut failed = entry(4, "upload", vec![journal_file("failed.html", "f")]);
+        failed.result = String::from("error");
+
+        let entries = vec![
+            entry(1, "upload", vec![journal_file("index.html", "a")]),
+            entry(
+                2,
+                "upload",
+                vec![
+                    journal_file("index.html", "b"),
+                    journal_file("drafts/one.html", "c"),
+                ],
+            ),
+            entry(3, "delete", vec![journal_file("drafts", "")]),
+            failed,
+        ];
+
+        let state = r.state_at(&entries, 1);
+        assert_eq!(state["index.html"].sha1_hash.as_deref(), Some("a"));
+
+        let state = r.state_at(&entries, 2);
+        assert_eq!(state.len(), 2);
+        assert_eq!(state["index.html"].sha1_hash.as_deref(), Some("b"));
+
+        let state = r.state_at(&entries, 4);
+        assert_eq!(state.keys().collect::<Vec<_>>(), vec!["index.html"]);
+    }
+
+    #[test]
+    fn site_entries_method() {
+        let r = Rollback::new();
+        let mut mine = entry(1, "upload", vec![journal_file("index.html", "a")]);
+        mine.site = Some(String::from("mysite"));
+        let mut other = entry(2, "upload", vec![journal_file("index.html", "b")]);
+        other.site = Some(String::from("othersite"));
+        let unknown = entry(3, "upload", vec![journal_file("index.html", "c")]);
+
+        let entries = r.site_entries(vec![mine, other, unknown], "mysite");
+        assert_eq!(entries.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    #[serial(env)]
+    fn changes_method() -> Result<(), NeocitiesErr> {
+        let _state_dir = TempStateDir::new()?;
+        let temp_dir = tempfile::tempdir()?;
+
+        let old = temp_dir.path().join("old.html");
+        fs::write(&old, "old")?;
+        let old_sha1 = objects::store(&old)?;
+
+        let r = Rollback::new();
+        let entries = vec![
+            entry(1, "upload", vec![journal_file("index.html", &old_sha1)]),
+            entry(
+                2,
+                "upload",
+                vec![
+                    journal_file("index.html", "new"),
+                    journal_file("added.html", "added"),
+                ],
+            ),
+        ];
+
+        let remote = ListResponse {
+            result: String::from("success"),
+            files: vec![
+                remote_file("index.html", "new"),
+                remote_file("added.html", "added"),
+                remote_file("untracked.html", "x"),
+            ],
+        };
+
+        let changes = r.changes(&entries, 1, &remote)?;
+        assert_eq!(paths(&changes.uploads), vec!["index.html"]);
+        assert_eq!(paths(&changes.deletes), vec!["added.html"]);
+
+        // rolling back to the latest deployment changes nothing
+        let changes = r.changes(&entries, 2, &remote)?;
+        assert_eq!(changes.uploads.is_empty(), true);
+        assert_eq!(changes.deletes.is_empty(), true);
+
+        assert_eq!(
+            matches!(
+                r.changes(&entries, 9, &remote),
+                Err(NeocitiesErr::UnknownDeployId)
+            ),
+            true
+        );
+
+        // the old contents must be in the object store
+        fs::remove_file(objects::object_path(&old_sha1))?;
+        assert_eq!(
+            matches!(
+                r.changes(&entries, 1, &remote),
+                Err(NeocitiesErr::MissingObject)
+            ),
+            true
+        );
+
+        let mut output = Vec::new();
+        r.write_changes(
+            &super::Changes {
+                uploads: vec![journal_file("index.html", "a")],
+                deletes: vec![],
+            },
+            1,
+            &mut output,
+        )?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("restore\x1b[0m index.html"), true);
+        assert_eq!(s.contains("1 restore(s), 0 delete(s)"), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn confirm_method() -> Result<(), NeocitiesErr> {
+        let r = Rollback::new();
+
+        let mut output = Vec::new();
+        assert_eq!(r.confirm(&b"what\ny\n"[..], &mut output)?, true);
+        assert_eq!(String::from_utf8(output)?.contains("Invalid input"), true);
+
+        let mut output = Vec::new();
+        assert_eq!(r.confirm(&b""[..], &mut output)?, false);
+
+        Ok(())
+    }
+}
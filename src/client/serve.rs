@@ -0,0 +1,511 @@
+use super::{command::Executable, filetype, ignore::Ignore, init::ProjectConfig, state};
+use crate::error::NeocitiesErr;
+use chrono::Local;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    thread,
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "serve";
+
+/// The port the preview server listens on when none is given
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// The page Neocities shows for a path that does not exist, if the website has one
+const NOT_FOUND_PAGE: &str = "404.html";
+
+/// Serves a local directory the way Neocities would serve it once uploaded
+pub struct Serve {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The options given to `serve`
+#[derive(Debug, PartialEq)]
+struct ServeOpts {
+    root: PathBuf,
+    port: u16,
+    /// Apply the file type rules of the supporter plan
+    supporter: bool,
+}
+
+/// A local directory served as a website
+#[derive(Debug, Clone)]
+struct Site {
+    root: PathBuf,
+    supporter: bool,
+    /// The paths the project leaves out of uploads
+    ignore: Ignore,
+}
+
+/// The content types Neocities sends, by file extension. Other extensions are sent as
+/// `application/octet-stream`.
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" | "less" | "sass" | "scss" => "text/css; charset=utf-8",
+        "js" => "application/javascript; charset=utf-8",
+        "json" | "geojson" | "map" => "application/json; charset=utf-8",
+        "webmanifest" | "manifest" | "webapp" => "application/manifest+json; charset=utf-8",
+        "txt" | "text" | "md" | "markdown" | "py" | "asc" | "csv" | "tsv" | "knowl" => {
+            "text/plain; charset=utf-8"
+        }
+        "xml" | "opml" | "osdx" => "application/xml; charset=utf-8",
+        "rss" => "application/rss+xml; charset=utf-8",
+        "atom" => "application/atom+xml; charset=utf-8",
+        "rdf" => "application/rdf+xml; charset=utf-8",
+        "kml" => "application/vnd.google-earth.kml+xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "apng" => "image/apng",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jxl" => "image/jxl",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "epub" => "application/epub+zip",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        "mid" | "midi" => "audio/midi",
+        "gltf" => "model/gltf+json",
+        "obj" | "mtl" => "text/plain; charset=utf-8",
+        "dae" => "model/vnd.collada+xml",
+        "gpg" | "pgp" | "key" => "application/pgp-keys",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decodes the `%XX` escapes of a URL path. Returns `None` if an escape is malformed or the
+/// result is not UTF-8.
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "",
+    }
+}
+
+impl Site {
+    /// Returns true if the file would be published by uploading the directory: it is not in the
+    /// state directory or ignored by the project, and Neocities accepts its type.
+    fn publishes(&self, file: &Path) -> bool {
+        if file.ancestors().any(state::is_state_dir) || self.ignore.is_ignored(file) {
+            return false;
+        }
+
+        match file.to_str() {
+            Some(f) => matches!(filetype::check(f, self.supporter), Ok(None)),
+            None => false,
+        }
+    }
+
+    /// Finds the file Neocities would serve for a URL path: the file itself, the `index.html` of
+    /// a directory, or the `.html` file of the same name for a path without an extension.
+    fn resolve(&self, url_path: &str) -> Option<PathBuf> {
+        let path = url_path.split(['?', '#']).next().unwrap_or_default();
+        let path = percent_decode(path)?;
+
+        let relative = PathBuf::from(path.trim_start_matches('/'));
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        let target = self.root.join(&relative);
+        let mut candidates = vec![target.join("index.html")];
+        if !path.ends_with('/') {
+            candidates.insert(0, target.clone());
+            if let Some(name) = relative.file_name().and_then(|n| n.to_str()) {
+                candidates.push(target.with_file_name(format!("{name}.html")));
+            }
+        }
+
+        candidates
+            .into_iter()
+            .find(|c| c.is_file() && self.contains(c) && self.publishes(c))
+    }
+
+    /// Returns true if the file, once symbolic links are followed, is inside the root. A link to a
+    /// file elsewhere would not be uploaded with the directory, so it is not served.
+    fn contains(&self, file: &Path) -> bool {
+        match (file.canonicalize(), self.root.canonicalize()) {
+            (Ok(f), Ok(root)) => f.starts_with(root),
+            _ => false,
+        }
+    }
+
+    /// Reads a request from a client and writes the response, which is the file at the path or
+    /// the site's `404.html`. Returns the method, the path and the status for the log.
+    fn respond(
+        &self,
+        reader: impl BufRead,
+        mut writer: impl Write,
+    ) -> Result<(String, String, u16), NeocitiesErr> {
+        let mut lines = reader.lines();
+        let request_line = lines.next().transpose()?.unwrap_or_default();
+
+        // the headers are not needed, but are read so that the client is not cut off mid-request
+        for line in lines {
+            if line?.is_empty() {
+                break;
+            }
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        let (status, file) = match method.as_str() {
+            "GET" | "HEAD" if path.starts_with('/') => match self.resolve(&path) {
+                Some(f) => (200, Some(f)),
+                None => (
+                    404,
+                    Some(self.root.join(NOT_FOUND_PAGE)).filter(|f| f.is_file()),
+                ),
+            },
+            "GET" | "HEAD" => (400, None),
+            _ => (405, None),
+        };
+
+        let (body, kind) = match &file {
+            Some(f) => (fs::read(f)?, content_type(f)),
+            None => (
+                format!("{status} {}\n", reason(status)).into_bytes(),
+                "text/plain; charset=utf-8",
+            ),
+        };
+
+        let head = format!(
+            "HTTP/1.1 {status} {}\r\nContent-Type: {kind}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            reason(status),
+            body.len()
+        );
+        writer.write_all(head.as_bytes())?;
+        if method != "HEAD" {
+            writer.write_all(&body)?;
+        }
+        writer.flush()?;
+
+        Ok((method, path, status))
+    }
+
+    fn handle(&self, stream: TcpStream) -> Result<(String, String, u16), NeocitiesErr> {
+        let reader = BufReader::new(stream.try_clone()?);
+        self.respond(reader, stream)
+    }
+}
+
+impl Serve {
+    /// A constructor that returns an instance of `Serve`.
+    pub fn new() -> Serve {
+        Serve {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m [<dir>] [--port <n>] [--supporter]"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<ServeOpts, NeocitiesErr> {
+        let mut opts = ServeOpts {
+            root: ProjectConfig::local_root()?,
+            port: DEFAULT_PORT,
+            supporter: filetype::is_supporter(),
+        };
+        let mut root: Option<PathBuf> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--supporter" => opts.supporter = true,
+                "-p" | "--port" => {
+                    opts.port = match args.get(i + 1).map(|n| n.parse::<u16>()) {
+                        Some(Ok(p)) => p,
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                a if !a.starts_with('-') && root.is_none() => root = Some(PathBuf::from(a)),
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 1;
+        }
+
+        if let Some(r) = root {
+            opts.root = r;
+        }
+        if !opts.root.is_dir() {
+            return Err(NeocitiesErr::InvalidPath);
+        }
+
+        Ok(opts)
+    }
+}
+
+impl Default for Serve {
+    fn default() -> Serve {
+        Serve::new()
+    }
+}
+
+impl Executable for Serve {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let opts = self.parse_opts(args)?;
+        let site = Site {
+            root: opts.root.clone(),
+            supporter: opts.supporter,
+            ignore: Ignore::load(Path::new("."))?,
+        };
+
+        let listener = TcpListener::bind(("127.0.0.1", opts.port))?;
+        let output = format!(
+            "Serving {} at \x1b[1;92mhttp://{}/\x1b[0m; press Ctrl-C to stop\n",
+            opts.root.display(),
+            listener.local_addr()?
+        );
+        self.write(output.as_str(), &mut stdout)?;
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+
+            let site = site.clone();
+            thread::spawn(move || {
+                let line = match site.handle(stream) {
+                    Ok((method, path, status)) => format!("{method} {path} {status}"),
+                    Err(e) => format!("\x1b[91mError\x1b[0m: {e}"),
+                };
+                let _ = writeln!(
+                    std::io::stdout(),
+                    "\x1b[90m[{}]\x1b[0m {line}",
+                    Local::now().format("%H:%M:%S")
+                );
+            });
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Preview a local directory, offline, as Neocities would serve it once \
+uploaded. The server listens on http://127.0.0.1:8080/ (set another port with `--port`). Like \
+Neocities, it serves the `index.html` of a directory, finds `about.html` for /about, sends your \
+`404.html` for paths that do not exist and uses the content types Neocities uses. Files that \
+Neocities would not accept, the paths in .neocitiesignore and the state directory are not served. \
+Without a directory, it serves the local root of the project set up by `init`, or the current \
+directory.";
+
+const DESC_SHORT: &str = "Preview a directory locally";
+
+#[cfg(test)]
+mod tests {
+    use super::{content_type, percent_decode, Serve, Site, DEFAULT_PORT, DESC, DESC_SHORT, KEY};
+    use crate::{
+        client::{command::Executable, ignore::Ignore},
+        error::NeocitiesErr,
+    };
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let s = Serve::new();
+        assert_eq!(s.get_usage().contains(KEY), true);
+        assert_eq!(s.get_short_desc(), DESC_SHORT);
+        assert_eq!(s.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let s = Serve::new();
+
+        let opts = s.parse_opts(vec![
+            String::from("tests/fixtures"),
+            String::from("--port"),
+            String::from("4000"),
+        ])?;
+        assert_eq!(opts.root, PathBuf::from("tests/fixtures"));
+        assert_eq!(opts.port, 4000);
+
+        assert_eq!(s.parse_opts(vec![])?.port, DEFAULT_PORT);
+        assert_eq!(s.parse_opts(vec![String::from("-p")]).is_err(), true);
+        assert_eq!(
+            s.parse_opts(vec![String::from("tests/fixtures/foo.html")])
+                .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn helper_functions() {
+        assert_eq!(
+            content_type(Path::new("a.HTML")),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(content_type(Path::new("a.woff2")), "font/woff2");
+        assert_eq!(content_type(Path::new("a")), "application/octet-stream");
+
+        assert_eq!(
+            percent_decode("/my%20cat.png").as_deref(),
+            Some("/my cat.png")
+        );
+        assert_eq!(percent_decode("/bad%2"), None);
+    }
+
+    fn site() -> Result<(tempfile::TempDir, Site), NeocitiesErr> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().to_path_buf();
+        fs::create_dir_all(root.join("blog"))?;
+        fs::write(root.join("index.html"), "home")?;
+        fs::write(root.join("about.html"), "about")?;
+        fs::write(root.join("blog/index.html"), "blog")?;
+        fs::write(root.join("my cat.png"), b"\x89PNG\r\n\x1a\n")?;
+        fs::write(root.join("setup.exe"), "MZ")?;
+        fs::write(root.join("notes.swp"), "draft")?;
+
+        let site = Site {
+            ignore: Ignore::parse(&root, "*.swp\n")?,
+            root,
+            supporter: false,
+        };
+        Ok((temp_dir, site))
+    }
+
+    #[test]
+    fn resolve_method() -> Result<(), NeocitiesErr> {
+        let (_temp_dir, site) = site()?;
+        let name = |p: &str| {
+            site.resolve(p)
+                .map(|f| f.strip_prefix(&site.root).unwrap().to_path_buf())
+        };
+
+        assert_eq!(name("/"), Some(PathBuf::from("index.html")));
+        assert_eq!(name("/about"), Some(PathBuf::from("about.html")));
+        assert_eq!(name("/about.html?x=1"), Some(PathBuf::from("about.html")));
+        assert_eq!(name("/blog"), Some(PathBuf::from("blog/index.html")));
+        assert_eq!(name("/blog/"), Some(PathBuf::from("blog/index.html")));
+        assert_eq!(name("/my%20cat.png"), Some(PathBuf::from("my cat.png")));
+        assert_eq!(name("/about/"), None);
+        assert_eq!(name("/../etc/passwd"), None);
+
+        // Neocities would not accept it, so it is not served
+        assert_eq!(name("/setup.exe"), None);
+        assert_eq!(name("/notes.swp"), None);
+
+        // a link that leads out of the root is not followed, while one inside it is
+        let outside = tempfile::tempdir()?;
+        fs::write(outside.path().join("secret.html"), "secret")?;
+        std::os::unix::fs::symlink(
+            outside.path().join("secret.html"),
+            site.root.join("secret.html"),
+        )?;
+        std::os::unix::fs::symlink(outside.path(), site.root.join("elsewhere"))?;
+        std::os::unix::fs::symlink(site.root.join("about.html"), site.root.join("me.html"))?;
+        assert_eq!(name("/secret.html"), None);
+        assert_eq!(name("/elsewhere/secret.html"), None);
+        assert_eq!(name("/me"), Some(PathBuf::from("me.html")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn respond_method() -> Result<(), NeocitiesErr> {
+        let (_temp_dir, site) = site()?;
+        let request = |r: &str| -> Result<(u16, String), NeocitiesErr> {
+            let mut output = Vec::new();
+            let (_, _, status) = site.respond(r.as_bytes(), &mut output)?;
+            Ok((status, String::from_utf8(output)?))
+        };
+
+        let (status, res) = request("GET /about HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        assert_eq!(status, 200);
+        assert_eq!(res.starts_with("HTTP/1.1 200 OK\r\n"), true);
+        assert_eq!(
+            res.contains("Content-Type: text/html; charset=utf-8\r\n"),
+            true
+        );
+        assert_eq!(res.ends_with("\r\n\r\nabout"), true);
+
+        let (status, res) = request("HEAD / HTTP/1.1\r\n\r\n")?;
+        assert_eq!(status, 200);
+        assert_eq!(res.ends_with("\r\n\r\n"), true);
+
+        let (status, res) = request("GET /missing HTTP/1.1\r\n\r\n")?;
+        assert_eq!(status, 404);
+        assert_eq!(res.ends_with("404 Not Found\n"), true);
+
+        // the site's own page for missing paths
+        fs::write(site.root.join("404.html"), "lost")?;
+        let (status, res) = request("GET /missing HTTP/1.1\r\n\r\n")?;
+        assert_eq!(status, 404);
+        assert_eq!(res.ends_with("lost"), true);
+
+        let (status, _) = request("POST / HTTP/1.1\r\n\r\n")?;
+        assert_eq!(status, 405);
+
+        Ok(())
+    }
+}
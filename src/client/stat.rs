@@ -0,0 +1,367 @@
+use super::{
+    command::Executable,
+    output::{format_bytes, Format},
+    trash::{public_url, sitename},
+    tree::RemoteTree,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::NcList,
+    },
+    error::NeocitiesErr,
+};
+use chrono::Local;
+use serde_derive::Serialize;
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "stat";
+
+/// Shows everything Neocities knows about files on a user's website
+pub struct Stat {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The details of a file or directory shown by `stat`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct StatReport {
+    /// The location of the file on the website, without a leading slash
+    path: String,
+    is_directory: bool,
+    /// The byte size of a file, or the total byte size of the files beneath a directory
+    size: u64,
+    /// The number of files beneath a directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<usize>,
+    sha1_hash: Option<String>,
+    /// The modification time as Neocities reports it, if it reports one
+    updated_at: Option<String>,
+    /// The modification time in the local time zone
+    updated_at_local: Option<String>,
+    /// Where the file is served from
+    url: String,
+}
+
+impl Stat {
+    /// A constructor that returns an instance of `Stat`.
+    pub fn new() -> Stat {
+        Stat {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m <path> [<path> ...] [--output <text|porcelain|json>]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<(Vec<String>, Format), NeocitiesErr> {
+        let mut paths: Vec<String> = Vec::new();
+        let mut format = Format::Text;
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((f, consumed)) = Format::from_args(&args[i..])? {
+                format = f;
+                i += consumed;
+                continue;
+            }
+
+            if args[i].starts_with('-') {
+                return Err(NeocitiesErr::InvalidArgument);
+            }
+            paths.push(args[i].clone());
+            i += 1;
+        }
+
+        if paths.is_empty() {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
+
+        Ok((paths, format))
+    }
+
+    /// Looks up a path in the listing of the website. A directory that holds files but is left
+    /// out of the listing has no hash or modification time.
+    fn report(
+        &self,
+        tree: &RemoteTree,
+        path: &str,
+        site: &str,
+    ) -> Result<StatReport, NeocitiesErr> {
+        let node = match tree.get(path) {
+            Some(n) => n,
+            None => return Err(NeocitiesErr::MissingFile),
+        };
+
+        let file = node.file.as_ref();
+        let updated_at_local = match file.map(|f| f.parse_timestamp()) {
+            Some(Ok(t)) => Some(
+                t.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S %z")
+                    .to_string(),
+            ),
+            _ => None,
+        };
+
+        Ok(StatReport {
+            path: node.path.clone(),
+            is_directory: node.is_directory(),
+            size: node.size,
+            files: node.is_directory().then(|| node.files().len()),
+            sha1_hash: file.and_then(|f| f.sha1_hash.clone()),
+            updated_at: file.map(|f| f.updated_at.clone()),
+            updated_at_local,
+            url: public_url(site, &node.path),
+        })
+    }
+
+    fn write_text(
+        &self,
+        reports: &[StatReport],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for (i, r) in reports.iter().enumerate() {
+            if i > 0 {
+                self.write("\n", &mut writer)?;
+            }
+
+            let (kind, size) = match r.files {
+                Some(n) => (
+                    "directory",
+                    format!(
+                        "{} ({}) in {n} file{}",
+                        r.size,
+                        format_bytes(r.size),
+                        if n == 1 { "" } else { "s" }
+                    ),
+                ),
+                None => ("file", format!("{} ({})", r.size, format_bytes(r.size))),
+            };
+            let updated = match (&r.updated_at, &r.updated_at_local) {
+                (Some(u), Some(l)) => format!("{u} ({l} local time)"),
+                (Some(u), None) => u.clone(),
+                _ => String::from("-"),
+            };
+
+            let output = format!(
+                "   Path: \x1b[1;92m{}\x1b[0m\n   Type: {kind}\n   Size: {size}\n  SHA-1: {}\nUpdated: {updated}\n    URL: {}\n",
+                r.path,
+                r.sha1_hash.as_deref().unwrap_or("-"),
+                r.url
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one line per path: its path, `file` or `directory`, its size in bytes, its SHA-1
+    /// hash, its modification time in RFC 3339 and its public URL, separated by tabs. Missing
+    /// values are `-`.
+    fn write_porcelain(
+        &self,
+        reports: &[StatReport],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for r in reports.iter() {
+            let updated = r
+                .updated_at
+                .as_deref()
+                .and_then(|u| chrono::DateTime::parse_from_rfc2822(u).ok())
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| String::from("-"));
+
+            let output = format!(
+                "{}\t{}\t{}\t{}\t{updated}\t{}\n",
+                r.path,
+                match r.is_directory {
+                    true => "directory",
+                    false => "file",
+                },
+                r.size,
+                r.sha1_hash.as_deref().unwrap_or("-"),
+                r.url
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Stat {
+    fn default() -> Stat {
+        Stat::new()
+    }
+}
+
+impl Executable for Stat {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let (paths, format) = self.parse_opts(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let tree = RemoteTree::from_list(&NcList::fetch(None)?);
+        let site = sitename()?;
+
+        let mut reports: Vec<StatReport> = Vec::new();
+        for path in paths.iter() {
+            reports.push(self.report(&tree, path, &site)?);
+        }
+
+        match format {
+            Format::Json => {
+                let output = format!("{}\n", serde_json::to_string_pretty(&reports)?);
+                self.write(output.as_str(), &mut stdout)?;
+            }
+            Format::Porcelain => self.write_porcelain(&reports, &mut stdout)?,
+            Format::Text => self.write_text(&reports, &mut stdout)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Show the details Neocities keeps about a file on your website: its size, its \
+SHA-1 hash, when it was last changed (also in your local time) and the URL it is served from. \
+For a directory, the size is the total of the files beneath it. Use `--output json` or \
+`--output porcelain` for scripts.";
+
+const DESC_SHORT: &str = "Show details of remote files";
+
+#[cfg(test)]
+mod tests {
+    use super::{Stat, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{command::Executable, output::Format, tree::RemoteTree},
+        error::NeocitiesErr,
+    };
+
+    #[test]
+    fn usage_desc_methods() {
+        let s = Stat::new();
+        assert_eq!(s.get_usage().contains(KEY), true);
+        assert_eq!(s.get_short_desc(), DESC_SHORT);
+        assert_eq!(s.get_long_desc(), DESC);
+    }
+
+    fn tree() -> RemoteTree {
+        RemoteTree::from_list(&ListResponse {
+            result: String::from("success"),
+            files: vec![
+                File {
+                    path: String::from("images/my cat.png"),
+                    is_directory: false,
+                    size: Some(2_048),
+                    updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+                    sha1_hash: Some(String::from("abc123")),
+                },
+                File {
+                    path: String::from("index.html"),
+                    is_directory: false,
+                    size: Some(100),
+                    updated_at: String::from("Sun, 14 Feb 2016 03:04:00 -0000"),
+                    sha1_hash: Some(String::from("def456")),
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let s = Stat::new();
+
+        let (paths, format) = s.parse_opts(vec![
+            String::from("index.html"),
+            String::from("about.html"),
+            String::from("--json"),
+        ])?;
+        assert_eq!(paths, vec!["index.html", "about.html"]);
+        assert_eq!(format, Format::Json);
+
+        assert_eq!(s.parse_opts(vec![]).is_err(), true);
+        assert_eq!(s.parse_opts(vec![String::from("-x")]).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn report_method() -> Result<(), NeocitiesErr> {
+        let s = Stat::new();
+        let tree = tree();
+
+        let r = s.report(&tree, "/images/my cat.png", "foo")?;
+        assert_eq!(r.path, "images/my cat.png");
+        assert_eq!(r.is_directory, false);
+        assert_eq!(r.size, 2_048);
+        assert_eq!(r.files, None);
+        assert_eq!(r.sha1_hash.as_deref(), Some("abc123"));
+        assert_eq!(r.updated_at_local.is_some(), true);
+        assert_eq!(r.url, "https://foo.neocities.org/images/my%20cat.png");
+
+        // a directory left out of the listing
+        let r = s.report(&tree, "images", "foo")?;
+        assert_eq!(r.is_directory, true);
+        assert_eq!(r.files, Some(1));
+        assert_eq!(r.updated_at, None);
+
+        assert_eq!(s.report(&tree, "missing.html", "foo").is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_methods() -> Result<(), NeocitiesErr> {
+        let s = Stat::new();
+        let tree = tree();
+        let reports = vec![
+            s.report(&tree, "index.html", "foo")?,
+            s.report(&tree, "images", "foo")?,
+        ];
+
+        let mut output = Vec::new();
+        s.write_text(&reports, &mut output)?;
+        let text = String::from_utf8(output)?;
+        assert_eq!(text.contains("  SHA-1: def456\n"), true);
+        assert_eq!(text.contains("   Size: 2048 (2.0 KiB) in 1 file\n"), true);
+        assert_eq!(
+            text.contains("    URL: https://foo.neocities.org/index.html\n"),
+            true
+        );
+
+        let mut output = Vec::new();
+        s.write_porcelain(&reports, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "index.html\tfile\t100\tdef456\t2016-02-14T03:04:00+00:00\thttps://foo.neocities.org/index.html\n\
+             images\tdirectory\t2048\t-\t-\thttps://foo.neocities.org/images\n"
+        );
+
+        Ok(())
+    }
+}
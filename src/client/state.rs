@@ -0,0 +1,87 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The environment variable that overrides the location of the state directory
+pub const ENV_STATE_DIR: &str = "NEOCITIES_STATE_DIR";
+
+/// The default location of the state directory, relative to the current working directory
+pub const DEFAULT_STATE_DIR: &str = ".neocities";
+
+/// Returns the directory where local state, such as the trash, is kept: the path in
+/// `NEOCITIES_STATE_DIR` if it is set, otherwise `.neocities` in the current working directory.
+pub fn dir() -> PathBuf {
+    match env::var(ENV_STATE_DIR) {
+        Ok(d) if !d.is_empty() => PathBuf::from(d),
+        _ => PathBuf::from(DEFAULT_STATE_DIR),
+    }
+}
+
+/// Returns true if a path is the state directory, so that it can be left out when a local
+/// directory is walked for upload or comparison.
+pub fn is_state_dir(path: &Path) -> bool {
+    match (fs::canonicalize(path), fs::canonicalize(dir())) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Points the state directory at a new temporary directory while it is held, for tests. The
+/// previous setting is restored when it is dropped, even if the test panics.
+#[cfg(test)]
+pub(crate) struct TempStateDir {
+    prev: Result<String, env::VarError>,
+    dir: tempfile::TempDir,
+}
+
+#[cfg(test)]
+impl TempStateDir {
+    pub(crate) fn new() -> std::io::Result<TempStateDir> {
+        let prev = env::var(ENV_STATE_DIR);
+        let dir = tempfile::tempdir()?;
+        env::set_var(ENV_STATE_DIR, dir.path());
+        Ok(TempStateDir { prev, dir })
+    }
+
+    /// Returns the temporary state directory.
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempStateDir {
+    fn drop(&mut self) {
+        match &self.prev {
+            Ok(p) => env::set_var(ENV_STATE_DIR, p),
+            Err(_) => env::remove_var(ENV_STATE_DIR),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dir, is_state_dir, TempStateDir, DEFAULT_STATE_DIR, ENV_STATE_DIR};
+    use serial_test::serial;
+    use std::{env, path::PathBuf};
+
+    #[test]
+    #[serial(env)]
+    fn dir_function() {
+        let prev = env::var(ENV_STATE_DIR);
+
+        {
+            let state_dir = TempStateDir::new().unwrap();
+            assert_eq!(dir(), state_dir.path().to_path_buf());
+            assert_eq!(is_state_dir(state_dir.path()), true);
+            assert_eq!(is_state_dir(&PathBuf::from("src")), false);
+
+            env::remove_var(ENV_STATE_DIR);
+            assert_eq!(dir(), PathBuf::from(DEFAULT_STATE_DIR));
+        }
+
+        // the previous setting is back once the temporary directory is dropped
+        assert_eq!(env::var(ENV_STATE_DIR), prev);
+    }
+}
@@ -0,0 +1,510 @@
+use super::{
+    command::Executable,
+    diff::Diff,
+    journal::{JournalEntry, JournalFile},
+    objects, state,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        info::NcInfo,
+        list::File,
+        upload::NcUpload,
+    },
+    error::NeocitiesErr,
+};
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use url::form_urlencoded::byte_serialize;
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "trash";
+
+/// The name of the directory, inside the state directory, where trashed files are kept
+pub const TRASH_DIR: &str = "trash";
+
+/// The name of the file that holds the metadata of a trash entry
+const META_FILE: &str = "meta.json";
+
+/// The name of the directory, inside a trash entry, that holds the copies of the files
+const FILES_DIR: &str = "files";
+
+/// Lists and restores files that were saved to the local trash by `delete --trash`.
+pub struct Trash {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// A file saved to the trash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashedFile {
+    /// The original path of the file on the website
+    pub path: String,
+    /// The byte size of the file
+    pub size: Option<i64>,
+    /// The SHA-1 hash of the file
+    pub sha1_hash: Option<String>,
+}
+
+/// The files saved to the trash by a single delete
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// Identifies the entry, for example `20240101-120000`
+    pub id: String,
+    /// The sitename of the website the files were deleted from
+    pub site: String,
+    /// A timestamp for the delete
+    pub deleted_at: String,
+    /// The files that were deleted
+    pub files: Vec<TrashedFile>,
+}
+
+impl TrashEntry {
+    /// Returns the directory where trash entries are kept.
+    pub fn dir() -> PathBuf {
+        state::dir().join(TRASH_DIR)
+    }
+
+    /// Returns the directory of this entry.
+    fn location(&self) -> PathBuf {
+        TrashEntry::dir().join(&self.id)
+    }
+
+    /// Returns the local path of the copy of a trashed file.
+    pub fn local_path(&self, path: &str) -> PathBuf {
+        self.location().join(FILES_DIR).join(path)
+    }
+
+    /// Downloads a copy of each file from the public website into a new trash entry. Directories
+    /// are skipped, since they are recreated as their files are restored. If a copy does not
+    /// match the SHA-1 hash in the listing, the entry is discarded and an error is returned.
+    pub fn collect(
+        site: &str,
+        files: &[File],
+        download: impl Fn(String) -> Result<Vec<u8>, NeocitiesErr>,
+    ) -> Result<TrashEntry, NeocitiesErr> {
+        let now = Utc::now();
+
+        // two deletes within the same second get separate entries
+        let base = now.format("%Y%m%d-%H%M%S").to_string();
+        let mut id = base.clone();
+        let mut n = 1;
+        while TrashEntry::dir().join(&id).exists() {
+            n += 1;
+            id = format!("{base}-{n}");
+        }
+
+        let mut entry = TrashEntry {
+            id,
+            site: site.to_string(),
+            deleted_at: now.to_rfc2822(),
+            files: Vec::new(),
+        };
+
+        if let Err(e) = entry.download_files(files, download) {
+            let _ = fs::remove_dir_all(entry.location());
+            return Err(e);
+        }
+
+        entry.write()?;
+        Ok(entry)
+    }
+
+    fn download_files(
+        &mut self,
+        files: &[File],
+        download: impl Fn(String) -> Result<Vec<u8>, NeocitiesErr>,
+    ) -> Result<(), NeocitiesErr> {
+        let diff = Diff::new();
+
+        for file in files.iter().filter(|f| !f.is_directory) {
+            let copy = self.local_path(&file.path);
+            if let Some(parent) = copy.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let contents = download(public_url(&self.site, &file.path))?;
+            fs::write(&copy, contents)?;
+
+            let sha1_hash = diff.hash(&copy)?;
+            if let Some(expected) = &file.sha1_hash {
+                if *expected != sha1_hash {
+                    return Err(NeocitiesErr::TrashMismatch);
+                }
+            }
+
+            self.files.push(TrashedFile {
+                path: file.path.clone(),
+                size: file.size,
+                sha1_hash: Some(sha1_hash),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reads the trash entry with the given id.
+    pub fn read(id: &str) -> Result<TrashEntry, NeocitiesErr> {
+        let meta = TrashEntry::dir().join(id).join(META_FILE);
+        if id.is_empty() || id.contains(['/', '\\']) || !meta.is_file() {
+            return Err(NeocitiesErr::UnknownTrashId);
+        }
+
+        let contents = fs::read_to_string(meta)?;
+        let entry: TrashEntry = serde_json::from_str(&contents)?;
+        Ok(entry)
+    }
+
+    /// Reads every trash entry, oldest first.
+    pub fn list() -> Result<Vec<TrashEntry>, NeocitiesErr> {
+        let dir = TrashEntry::dir();
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries: Vec<TrashEntry> = Vec::new();
+        for e in fs::read_dir(dir)? {
+            let e = e?;
+            if e.path().join(META_FILE).is_file() {
+                let contents = fs::read_to_string(e.path().join(META_FILE))?;
+                entries.push(serde_json::from_str(&contents)?);
+            }
+        }
+
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(entries)
+    }
+
+    /// Writes the metadata of the entry next to its files.
+    fn write(&self) -> Result<(), NeocitiesErr> {
+        fs::create_dir_all(self.location())?;
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(self.location().join(META_FILE), format!("{contents}\n"))?;
+        Ok(())
+    }
+}
+
+/// Returns the public URL of a file on a Neocities website.
+pub fn public_url(site: &str, path: &str) -> String {
+    let segments: Vec<String> = path
+        .trim_matches('/')
+        .split('/')
+        .map(|s| {
+            byte_serialize(s.as_bytes())
+                .collect::<String>()
+                .replace('+', "%20")
+        })
+        .collect();
+
+    format!("https://{site}.neocities.org/{}", segments.join("/"))
+}
+
+/// Returns the sitename of the user's website: the username in the environment if it is set,
+/// otherwise the sitename that belongs to the API key.
+pub fn sitename() -> Result<String, NeocitiesErr> {
+    if let Some(user) = Credentials::new().get_username() {
+        return Ok(user);
+    }
+
+    let res = NcInfo::fetch(&Vec::new())?;
+    Ok(res.info.sitename)
+}
+
+impl Trash {
+    /// A constructor that returns an instance of `Trash`.
+    pub fn new() -> Trash {
+        Trash {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m <list | restore <id> [<path> ...]>"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_list(
+        &self,
+        entries: &[TrashEntry],
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        if entries.is_empty() {
+            self.write("The trash is empty.\n", &mut writer)?;
+            return Ok(());
+        }
+
+        for entry in entries.iter() {
+            let output = format!(
+                "\x1b[1;93m{}\x1b[0m {} ({}, {} file(s))\n",
+                entry.id,
+                entry.deleted_at,
+                entry.site,
+                entry.files.len()
+            );
+            self.write(output.as_str(), &mut writer)?;
+
+            for file in entry.files.iter() {
+                let output = format!("  \x1b[92m{}\x1b[0m\n", file.path);
+                self.write(output.as_str(), &mut writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pairs the local copy of each trashed file with its original path. If paths are given, only
+    /// those files are selected. Files are only restored to the website they were deleted from.
+    fn restore_files(
+        &self,
+        entry: &TrashEntry,
+        site: &str,
+        paths: &[String],
+    ) -> Result<Vec<(String, String)>, NeocitiesErr> {
+        if entry.site != site {
+            return Err(NeocitiesErr::TrashSiteMismatch);
+        }
+
+        let mut files: Vec<(String, String)> = Vec::new();
+
+        for file in entry.files.iter() {
+            if !paths.is_empty() && !paths.iter().any(|p| p.trim_matches('/') == file.path) {
+                continue;
+            }
+
+            match entry.local_path(&file.path).to_str() {
+                Some(local) => files.push((local.to_string(), file.path.clone())),
+                None => return Err(NeocitiesErr::InvalidPath),
+            }
+        }
+
+        if files.is_empty() || (!paths.is_empty() && files.len() != paths.len()) {
+            return Err(NeocitiesErr::MissingFile);
+        }
+
+        Ok(files)
+    }
+}
+
+impl Default for Trash {
+    fn default() -> Trash {
+        Trash::new()
+    }
+}
+
+impl Executable for Trash {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        match args.first().map(|a| a.as_str()) {
+            Some("list") => {
+                let entries = TrashEntry::list()?;
+                self.write_list(&entries, &mut stdout)?;
+            }
+            Some("restore") => {
+                let id = match args.get(1) {
+                    Some(id) => id,
+                    None => return Err(NeocitiesErr::InvalidArgument),
+                };
+
+                if !Credentials::have_env_vars() {
+                    self.write(ENV_VAR_MSG, &mut stdout)?;
+                    return Ok(());
+                }
+
+                let entry = TrashEntry::read(id)?;
+                let site = sitename()?;
+                let files = self.restore_files(&entry, &site, &args[2..])?;
+
+                for (_, path) in files.iter() {
+                    let output = format!("\x1b[1;92mrestore\x1b[0m {path}\n");
+                    self.write(output.as_str(), &mut stdout)?;
+                }
+
+                let mut journal_files: Vec<JournalFile> = Vec::new();
+                for f in entry.files.iter() {
+                    if files.iter().any(|(_, path)| *path == f.path) {
+                        journal_files.push(JournalFile {
+                            path: f.path.clone(),
+                            size: f.size,
+                            sha1_hash: Some(objects::store(&entry.local_path(&f.path))?),
+                        });
+                    }
+                }
+
+                let data = NcUpload::fetch_as(files)?;
+                JournalEntry::new("upload", KEY, Some(site), journal_files)
+                    .respond(&data.result, data.error_type.clone(), &data.message)
+                    .append()?;
+                let output = format!(
+                    "\x1b[93mStatus\x1b[0m: {} - {}\n",
+                    data.result, data.message
+                );
+                self.write(output.as_str(), &mut stdout)?;
+
+                if data.result != "success" {
+                    return Err(NeocitiesErr::RequestFailed);
+                }
+            }
+            _ => {
+                let output = format!("{}\nusage: {}\n", self.get_long_desc(), self.get_usage());
+                self.write(output.as_str(), &mut stdout)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "List and restore files saved to the local trash. Files are saved to the \
+trash when they are deleted with `delete --trash`, which downloads a copy of each file from your \
+public site before deleting it. `trash restore <id>` uploads the files of an entry back to their \
+original paths, on the website they were deleted from; give paths after the id to restore only \
+those files. The trash is kept in .neocities/trash, or in the directory named by \
+NEOCITIES_STATE_DIR.";
+
+const DESC_SHORT: &str = "List and restore deleted files";
+
+#[cfg(test)]
+mod tests {
+    use super::{public_url, Trash, TrashEntry, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::list::File,
+        client::{command::Executable, state::TempStateDir},
+        error::NeocitiesErr,
+    };
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    fn usage_desc_methods() {
+        let t = Trash::new();
+        assert_eq!(t.get_usage().contains(KEY), true);
+        assert_eq!(t.get_short_desc(), DESC_SHORT);
+        assert_eq!(t.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn public_url_function() {
+        assert_eq!(
+            public_url("foo", "/images/my file&1.png"),
+            "https://foo.neocities.org/images/my%20file%261.png"
+        );
+    }
+
+    fn file(path: &str, is_directory: bool, sha1_hash: Option<&str>) -> File {
+        File {
+            path: String::from(path),
+            is_directory,
+            size: sha1_hash.map(|_| 13),
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: sha1_hash.map(|s| s.to_string()),
+        }
+    }
+
+    /// Runs a test with the state directory set to a new temporary directory
+    fn with_state_dir(test: impl Fn() -> Result<(), NeocitiesErr>) -> Result<(), NeocitiesErr> {
+        let _state_dir = TempStateDir::new()?;
+        test()
+    }
+
+    #[test]
+    #[serial(env)]
+    fn collect_list_and_restore() -> Result<(), NeocitiesErr> {
+        with_state_dir(|| {
+            let files = vec![
+                file("images", true, None),
+                file(
+                    "images/a.html",
+                    false,
+                    Some("0a0a9f2a6772942557ab5355d76af442f8f65e01"),
+                ),
+            ];
+
+            let download = |url: String| {
+                assert_eq!(url, "https://foo.neocities.org/images/a.html");
+                Ok(b"Hello, World!".to_vec())
+            };
+
+            let first = TrashEntry::collect("foo", &files, download)?;
+            let second = TrashEntry::collect("foo", &files, download)?;
+            assert_ne!(first.id, second.id);
+
+            assert_eq!(first.files.len(), 1);
+            assert_eq!(first.files[0].path, "images/a.html");
+            assert_eq!(
+                fs::read_to_string(first.local_path("images/a.html"))?,
+                "Hello, World!"
+            );
+
+            assert_eq!(TrashEntry::read(&first.id)?, first);
+            assert_eq!(TrashEntry::list()?, vec![first.clone(), second]);
+            assert_eq!(TrashEntry::read("missing").is_err(), true);
+            assert_eq!(TrashEntry::read("../trash").is_err(), true);
+
+            let t = Trash::new();
+            let restore = t.restore_files(&first, "foo", &[])?;
+            assert_eq!(restore[0].1, "images/a.html");
+            assert_eq!(restore[0].0.ends_with("files/images/a.html"), true);
+            assert_eq!(
+                t.restore_files(&first, "foo", &["nope.html".to_string()])
+                    .is_err(),
+                true
+            );
+
+            // files are never restored into another website
+            assert_eq!(
+                matches!(
+                    t.restore_files(&first, "bar", &[]),
+                    Err(NeocitiesErr::TrashSiteMismatch)
+                ),
+                true
+            );
+
+            let mut output = Vec::new();
+            t.write_list(&TrashEntry::list()?, &mut output)?;
+            let s = String::from_utf8(output)?;
+            assert_eq!(s.contains(&first.id), true);
+            assert_eq!(s.contains("images/a.html"), true);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[serial(env)]
+    fn collect_rejects_mismatched_copy() -> Result<(), NeocitiesErr> {
+        with_state_dir(|| {
+            let files = vec![file("a.html", false, Some("expected"))];
+
+            let res = TrashEntry::collect("foo", &files, |_| Ok(b"stale".to_vec()));
+            assert_eq!(matches!(res, Err(NeocitiesErr::TrashMismatch)), true);
+
+            // nothing is left behind in the trash
+            assert_eq!(TrashEntry::list()?.is_empty(), true);
+            assert_eq!(
+                fs::read_dir(TrashEntry::dir())
+                    .map(|d| d.count())
+                    .unwrap_or(0),
+                0
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,200 @@
+use crate::api::list::{File, ListResponse};
+use std::collections::BTreeMap;
+
+/// The files of a Neocities website arranged as a hierarchy, built from the flat listing returned
+/// by `/api/list`. Each directory knows the total size of the files beneath it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTree {
+    root: Node,
+}
+
+/// A file or directory in a `RemoteTree`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// The last component of the path, or an empty string for the root
+    pub name: String,
+    /// The full path on the website, without a leading slash
+    pub path: String,
+    /// The entry from the listing. A directory only implied by the paths of its files has none.
+    pub file: Option<File>,
+    /// The byte size of a file, or the total byte size of the files beneath a directory
+    pub size: u64,
+    /// The entries in a directory, sorted by name
+    pub children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn new(name: &str, path: &str) -> Node {
+        Node {
+            name: name.to_string(),
+            path: path.to_string(),
+            file: None,
+            size: 0,
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if the node is a directory, including the root.
+    pub fn is_directory(&self) -> bool {
+        match &self.file {
+            Some(f) => f.is_directory,
+            None => true,
+        }
+    }
+
+    /// Returns every file beneath the node, or the node itself if it is a file, in path order.
+    pub fn files(&self) -> Vec<&File> {
+        let mut files: Vec<&File> = Vec::new();
+        if let Some(f) = &self.file {
+            if !f.is_directory {
+                files.push(f);
+            }
+        }
+        for child in self.children.values() {
+            files.extend(child.files());
+        }
+        files
+    }
+}
+
+impl RemoteTree {
+    /// Builds the tree from a listing. The listing may be in any order, and may leave out
+    /// directories that contain files.
+    pub fn from_list(list: &ListResponse) -> RemoteTree {
+        let mut root = Node::new("", "");
+
+        for file in list.files.iter() {
+            let path = file.path.trim_matches('/');
+            if path.is_empty() {
+                continue;
+            }
+
+            let size = match file.is_directory {
+                true => 0,
+                false => u64::try_from(file.size.unwrap_or_default()).unwrap_or_default(),
+            };
+
+            let mut node = &mut root;
+            node.size += size;
+
+            let mut walked = String::new();
+            for name in path.split('/') {
+                if !walked.is_empty() {
+                    walked.push('/');
+                }
+                walked.push_str(name);
+
+                node = node
+                    .children
+                    .entry(name.to_string())
+                    .or_insert_with(|| Node::new(name, &walked));
+                node.size += size;
+            }
+
+            node.file = Some(file.clone());
+        }
+
+        RemoteTree { root }
+    }
+
+    /// Returns the root of the website.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Returns the file or directory at a path, if the website has one. An empty path, or `/`,
+    /// is the root.
+    pub fn get(&self, path: &str) -> Option<&Node> {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            return Some(&self.root);
+        }
+
+        let mut node = &self.root;
+        for name in path.split('/') {
+            node = node.children.get(name)?;
+        }
+        Some(node)
+    }
+
+    /// Returns true if the website has a file or directory at a path.
+    pub fn contains(&self, path: &str) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Returns true if the path is a directory on the website.
+    pub fn is_directory(&self, path: &str) -> bool {
+        self.get(path).is_some_and(|n| n.is_directory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RemoteTree;
+    use crate::api::list::{File, ListResponse};
+
+    fn file(path: &str, size: Option<i64>) -> File {
+        File {
+            path: path.to_string(),
+            is_directory: size.is_none(),
+            size,
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: None,
+        }
+    }
+
+    fn list() -> ListResponse {
+        ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("index.html", Some(100)),
+                file("images/icons/b.png", Some(1000)),
+                file("images", None),
+                file("images/a.png", Some(2000)),
+            ],
+        }
+    }
+
+    #[test]
+    fn from_list_method() {
+        let tree = RemoteTree::from_list(&list());
+
+        assert_eq!(tree.root().size, 3100);
+        assert_eq!(
+            tree.root().children.keys().collect::<Vec<&String>>(),
+            vec!["images", "index.html"]
+        );
+
+        let images = tree.get("/images/").unwrap();
+        assert_eq!(images.size, 3000);
+        assert_eq!(images.file.is_some(), true);
+
+        // a directory left out of the listing is implied by its files
+        let icons = tree.get("images/icons").unwrap();
+        assert_eq!(icons.file, None);
+        assert_eq!(icons.path, "images/icons");
+        assert_eq!(icons.is_directory(), true);
+        assert_eq!(icons.size, 1000);
+    }
+
+    #[test]
+    fn lookup_methods() {
+        let tree = RemoteTree::from_list(&list());
+
+        assert_eq!(tree.contains("index.html"), true);
+        assert_eq!(tree.contains("about.html"), false);
+        assert_eq!(tree.is_directory("images"), true);
+        assert_eq!(tree.is_directory("index.html"), false);
+        assert_eq!(tree.get("").unwrap().name, "");
+
+        let paths: Vec<&str> = tree
+            .get("images")
+            .unwrap()
+            .files()
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["images/a.png", "images/icons/b.png"]);
+        assert_eq!(tree.root().files().len(), 3);
+    }
+}
@@ -1,15 +1,43 @@
-use super::command::Executable;
+use super::{
+    command::Executable,
+    diff::Diff,
+    filetype,
+    ignore::Ignore,
+    journal::{JournalEntry, JournalFile},
+    objects,
+    output::{self, FileReport, Format, Report},
+    progress::{Progress, Style},
+    state, trash,
+    usage::{self, Limits},
+};
 use crate::{
     api::{
         credentials::{Credentials, ENV_VAR_MSG},
+        http::Transfer,
+        list::{ListResponse, NcList},
         upload::{NcUpload, UploadResponse},
     },
     error::NeocitiesErr,
 };
+use chrono::Utc;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 /// The string literal a user must type to run functionality in this module
 pub const KEY: &'static str = "upload";
 
+/// The number of files sent in each request, unless `--batch-size` is given
+pub const DEFAULT_BATCH_SIZE: usize = 50;
+
+/// The name of the file, inside the state directory, that tracks the progress of an upload
+pub const SESSION_FILE: &str = "upload.session.json";
+
 /// Uploads files to a Neocities user's site. The Neocities API allows a user to upload as many
 /// files as desired, as long as the entire request stays within the disk space limit.
 pub struct Upload {
@@ -18,12 +46,149 @@ pub struct Upload {
     long: String,
 }
 
+/// Options that alter how files are uploaded
+struct UploadOpts {
+    /// Prepare the request and report what would be sent, without sending it
+    dry_run: bool,
+    /// The format in which the results are written to stdout
+    format: Format,
+    /// Continue the last upload that did not finish
+    resume: bool,
+    /// Skip the files whose contents already match the remote
+    changed_only: bool,
+    /// Check the remote listing for every file once it is uploaded
+    verify: bool,
+    /// Apply the file type rules and storage limit of the supporter plan
+    supporter: bool,
+    /// The storage limit of the account, instead of the plan's
+    storage_limit: Option<u64>,
+    /// The largest file the account may upload, instead of the Neocities default
+    max_file_size: Option<u64>,
+    /// The number of files sent in each request
+    batch_size: usize,
+    /// The number of requests in flight at once
+    jobs: usize,
+    /// The most bytes to send per second, across every request
+    limit_rate: Option<u64>,
+}
+
+/// The progress of an upload, kept in the state directory until every file has landed so that an
+/// interrupted or partly failed upload can be continued with `--resume`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadSession {
+    /// A timestamp for the start of the upload
+    pub started_at: String,
+    /// Every local file in the upload
+    pub files: Vec<String>,
+    /// The files that are known to have been uploaded
+    pub uploaded: Vec<String>,
+    /// The files that failed in the most recent attempt, with the reason
+    pub failed: Vec<FailedFile>,
+}
+
+/// A file that could not be uploaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedFile {
+    /// The local path of the file
+    pub path: String,
+    /// The error returned for the file
+    pub error: String,
+}
+
+/// What happened to each file sent in a batch: the server's message if it was uploaded, or the
+/// error if it was not
+type BatchOutcome = Vec<(String, Result<String, String>)>;
+
+impl UploadSession {
+    /// Starts a session for the given files.
+    pub fn new(files: Vec<String>) -> UploadSession {
+        UploadSession {
+            started_at: Utc::now().to_rfc2822(),
+            files,
+            uploaded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Returns the location of the session file.
+    pub fn path() -> PathBuf {
+        state::dir().join(SESSION_FILE)
+    }
+
+    /// Reads the session of the last upload that did not finish.
+    pub fn read() -> Result<UploadSession, NeocitiesErr> {
+        let path = UploadSession::path();
+        if !path.is_file() {
+            return Err(NeocitiesErr::NoUploadToResume);
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let session: UploadSession = serde_json::from_str(&contents)?;
+        Ok(session)
+    }
+
+    /// Writes the session to the state directory.
+    pub fn write(&self) -> Result<(), NeocitiesErr> {
+        fs::create_dir_all(state::dir())?;
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(UploadSession::path(), format!("{contents}\n"))?;
+        Ok(())
+    }
+
+    /// Removes the session file, once every file has been uploaded.
+    pub fn remove() -> Result<(), NeocitiesErr> {
+        let path = UploadSession::path();
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the files that have not been uploaded yet.
+    pub fn pending(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|f| !self.uploaded.contains(f))
+            .cloned()
+            .collect()
+    }
+
+    /// Marks files as uploaded without sending them, since they already match the remote. A
+    /// file that failed before is no longer counted as failed.
+    pub fn skip(&mut self, paths: &[String]) {
+        for path in paths.iter() {
+            self.failed.retain(|f| f.path != *path);
+            if !self.uploaded.contains(path) {
+                self.uploaded.push(path.clone());
+            }
+        }
+    }
+
+    /// Records what happened to the files of a batch.
+    fn record(&mut self, outcome: &BatchOutcome) {
+        for (path, result) in outcome.iter() {
+            self.failed.retain(|f| f.path != *path);
+            match result {
+                Ok(_) => {
+                    if !self.uploaded.contains(path) {
+                        self.uploaded.push(path.clone());
+                    }
+                }
+                Err(e) => self.failed.push(FailedFile {
+                    path: path.clone(),
+                    error: e.clone(),
+                }),
+            }
+        }
+    }
+}
+
 impl Upload {
     /// A constructor that returns an instance of `Upload`.
     pub fn new() -> Upload {
         Upload {
             usage: String::from(format!(
-                "\x1b[1;32m{}\x1b[0m <filename> [<another filename>]",
+                "\x1b[1;32m{}\x1b[0m [--dry-run] [--changed-only] [--verify] [--supporter] [--storage-limit <size>] [--max-file-size <size>] [--batch-size <n>] [-j <jobs>] [--limit-rate <rate>] [--output <text|json>] <filename|dir|glob> [<another>] | --resume",
                 KEY
             )),
             short: String::from(DESC_SHORT),
@@ -37,20 +202,530 @@ impl Upload {
         Ok(())
     }
 
-    fn parse_response(
+    fn parse_opts(&self, args: Vec<String>) -> Result<(UploadOpts, Vec<String>), NeocitiesErr> {
+        let mut opts = UploadOpts {
+            dry_run: false,
+            format: Format::Text,
+            resume: false,
+            changed_only: false,
+            verify: false,
+            supporter: false,
+            storage_limit: None,
+            max_file_size: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            jobs: 1,
+            limit_rate: None,
+        };
+        let mut rest: Vec<String> = Vec::new();
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((format, consumed)) = Format::from_args(&args[i..])? {
+                opts.format = format;
+                i += consumed;
+                continue;
+            }
+
+            match args[i].as_str() {
+                "-n" | "--dry-run" => opts.dry_run = true,
+                "--resume" => opts.resume = true,
+                "--changed-only" => opts.changed_only = true,
+                "--verify" => opts.verify = true,
+                "--supporter" => opts.supporter = true,
+                "--batch-size" => {
+                    opts.batch_size = match args.get(i + 1).map(|n| n.parse::<usize>()) {
+                        Some(Ok(n)) if n > 0 => n,
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                "-j" | "--jobs" => {
+                    opts.jobs = match args.get(i + 1).map(|n| n.parse::<usize>()) {
+                        Some(Ok(n)) if n > 0 => n,
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                "--storage-limit" | "--max-file-size" => {
+                    let size = match args.get(i + 1) {
+                        Some(v) => Some(output::parse_bytes(v)?),
+                        None => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    match args[i].as_str() {
+                        "--storage-limit" => opts.storage_limit = size,
+                        _ => opts.max_file_size = size,
+                    }
+                    i += 1;
+                }
+                "--limit-rate" => {
+                    opts.limit_rate = match args.get(i + 1) {
+                        Some(rate) => Some(output::parse_bytes(rate)?),
+                        None => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                _ => rest.push(args[i].clone()),
+            }
+            i += 1;
+        }
+
+        Ok((opts, rest))
+    }
+
+    /// Expands the arguments into a list of files: directories are walked recursively and glob
+    /// patterns are matched against the local file system. Files keep the path they were given as,
+    /// since it also determines their path on the remote. The paths found by walking or matching
+    /// leave out those in the `.neocitiesignore` of the current directory, while a file named on
+    /// its own is always sent.
+    fn expand_paths(&self, args: &[String]) -> Result<Vec<String>, NeocitiesErr> {
+        let ignore = Ignore::load(Path::new("."))?;
+        let mut files: Vec<String> = Vec::new();
+
+        for arg in args.iter() {
+            let mut found: Vec<PathBuf> = Vec::new();
+
+            if arg.contains(['*', '?', '[']) {
+                let paths = match glob::glob(arg) {
+                    Ok(p) => p,
+                    Err(_) => return Err(NeocitiesErr::InvalidArgument),
+                };
+
+                for path in paths.flatten().filter(|p| !ignore.is_ignored(p)) {
+                    found.push(path);
+                }
+
+                if found.is_empty() {
+                    return Err(NeocitiesErr::MissingFile);
+                }
+            } else {
+                let path = PathBuf::from(arg);
+                if !path.exists() {
+                    return Err(NeocitiesErr::MissingFile);
+                }
+                found.push(path);
+            }
+
+            for path in found.into_iter() {
+                self.walk(path, &ignore, &mut files)?;
+            }
+        }
+
+        // a file matched by several arguments is only sent once
+        let mut seen = std::collections::HashSet::new();
+        files.retain(|f| seen.insert(f.clone()));
+
+        Ok(files)
+    }
+
+    fn walk(
+        &self,
+        path: PathBuf,
+        ignore: &Ignore,
+        files: &mut Vec<String>,
+    ) -> Result<(), NeocitiesErr> {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(&path)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| !state::is_state_dir(p) && !ignore.is_ignored(p))
+                .collect();
+            entries.sort();
+
+            for entry in entries.into_iter() {
+                self.walk(entry, ignore, files)?;
+            }
+        } else if let Some(p) = path.to_str() {
+            files.push(p.to_string());
+        } else {
+            return Err(NeocitiesErr::InvalidPath);
+        }
+
+        Ok(())
+    }
+
+    /// Describes each file to be uploaded. If a remote listing is given, each file is also marked
+    /// as `new`, `changed` or `unchanged` compared with the remote.
+    fn file_reports(
+        &self,
+        paths: &[String],
+        remote: Option<&ListResponse>,
+    ) -> Result<Vec<FileReport>, NeocitiesErr> {
+        let diff = Diff::new();
+        let mut reports: Vec<FileReport> = Vec::new();
+
+        for path in paths.iter() {
+            let path_buf = PathBuf::from(path);
+            let size = i64::try_from(fs::metadata(&path_buf)?.len())?;
+            let sha1_hash = diff.hash(&path_buf)?;
+
+            let status = match remote {
+                Some(lr) => {
+                    let remote_path = diff.format_path(&path_buf)?;
+                    match lr.files.iter().find(|f| f.path == remote_path) {
+                        Some(f) if f.sha1_hash.as_ref() == Some(&sha1_hash) => Some("unchanged"),
+                        Some(_) => Some("changed"),
+                        None => Some("new"),
+                    }
+                }
+                None => None,
+            };
+
+            reports.push(FileReport {
+                path: path.clone(),
+                size: Some(size),
+                sha1_hash: Some(sha1_hash),
+                status: status.map(|s| s.to_string()),
+                message: None,
+            });
+        }
+
+        Ok(reports)
+    }
+
+    /// Describes each file for a dry run, compared with the remote. The files a real upload would
+    /// skip are marked `skipped`, as in the report of the real upload.
+    fn dry_run_reports(
+        &self,
+        paths: &[String],
+        remote: &ListResponse,
+        skipped: &[String],
+    ) -> Result<Vec<FileReport>, NeocitiesErr> {
+        let mut reports = self.file_reports(paths, Some(remote))?;
+        for report in reports.iter_mut().filter(|r| skipped.contains(&r.path)) {
+            report.status = Some(String::from("skipped"));
+        }
+        Ok(reports)
+    }
+
+    /// Returns the files whose SHA-1 hash matches the remote listing, which need not be sent.
+    fn unchanged(
+        &self,
+        paths: &[String],
+        remote: &ListResponse,
+    ) -> Result<Vec<String>, NeocitiesErr> {
+        Ok(self
+            .file_reports(paths, Some(remote))?
+            .into_iter()
+            .filter(|r| r.status.as_deref() == Some("unchanged"))
+            .map(|r| r.path)
+            .collect())
+    }
+
+    /// Checks every file against the types Neocities accepts, and returns each file it would
+    /// reject, with the reason.
+    pub(crate) fn validate(
+        &self,
+        paths: &[String],
+        supporter: bool,
+        max_file_size: u64,
+    ) -> Result<Vec<FailedFile>, NeocitiesErr> {
+        let mut rejected: Vec<FailedFile> = Vec::new();
+
+        for path in paths.iter() {
+            let size = fs::metadata(path)?.len();
+            if size > max_file_size {
+                rejected.push(FailedFile {
+                    path: path.clone(),
+                    error: format!(
+                        "is {}, over the limit of {} per file",
+                        output::format_bytes(size),
+                        output::format_bytes(max_file_size)
+                    ),
+                });
+            } else if let Some(reason) = filetype::check(path, supporter)? {
+                rejected.push(FailedFile {
+                    path: path.clone(),
+                    error: reason,
+                });
+            }
+        }
+
+        Ok(rejected)
+    }
+
+    pub(crate) fn write_rejected(
+        &self,
+        rejected: &[FailedFile],
+        format: Format,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        if format == Format::Json {
+            let paths: Vec<String> = rejected.iter().map(|r| r.path.clone()).collect();
+            let mut reports = self.file_reports(&paths, None)?;
+            for (report, r) in reports.iter_mut().zip(rejected.iter()) {
+                report.status = Some(String::from("rejected"));
+                report.message = Some(r.error.clone());
+            }
+
+            let mut report = Report::new(KEY, false, reports);
+            report.result = Some(String::from("error"));
+            report.message = Some(NeocitiesErr::DisallowedFileType.to_string());
+            return report.write_json(&mut writer);
+        }
+
+        for r in rejected.iter() {
+            let output = format!("  \x1b[91mrejected\x1b[0m {}: {}\n", r.path, r.error);
+            self.write(output.as_str(), &mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Refuses an upload that would take the website past its storage limit, and warns about one
+    /// that would bring it close.
+    pub(crate) fn check_quota(
+        &self,
+        remote: &ListResponse,
+        paths: &[String],
+        limits: &Limits,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let diff = Diff::new();
+        let mut uploads: Vec<(String, u64)> = Vec::new();
+        for path in paths.iter() {
+            let path_buf = PathBuf::from(path);
+            uploads.push((diff.format_path(&path_buf)?, fs::metadata(&path_buf)?.len()));
+        }
+
+        let projected = usage::projected(remote, &uploads);
+        let summary = format!(
+            "this upload brings your site to {} of its {} storage",
+            output::format_bytes(projected),
+            output::format_bytes(limits.storage)
+        );
+
+        if projected > limits.storage {
+            let output = format!("\x1b[91mOver quota\x1b[0m: {summary}\n");
+            self.write(output.as_str(), &mut writer)?;
+            return Err(NeocitiesErr::QuotaExceeded);
+        }
+
+        if projected as f64 > limits.storage as f64 * usage::WARN_RATIO {
+            let output = format!("\x1b[93mWarning\x1b[0m: {summary}\n");
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compares uploaded files with the remote listing, and returns each file that is missing
+    /// from the remote or whose size or SHA-1 hash differs from the local file.
+    fn verify(
+        &self,
+        expected: &[FileReport],
+        remote: &ListResponse,
+    ) -> Result<Vec<FailedFile>, NeocitiesErr> {
+        let diff = Diff::new();
+        let mut mismatches: Vec<FailedFile> = Vec::new();
+
+        for file in expected.iter() {
+            let remote_path = diff.format_path(&PathBuf::from(&file.path))?;
+            let found = remote
+                .files
+                .iter()
+                .find(|f| f.path == remote_path && !f.is_directory);
+
+            let reason = match found {
+                None => Some(String::from("missing from your site")),
+                Some(f) if f.size != file.size => Some(format!(
+                    "your site has {} bytes, expected {}",
+                    f.size.unwrap_or_default(),
+                    file.size.unwrap_or_default()
+                )),
+                Some(f) if f.sha1_hash != file.sha1_hash => Some(format!(
+                    "your site has SHA-1 {}, expected {}",
+                    f.sha1_hash.as_deref().unwrap_or_default(),
+                    file.sha1_hash.as_deref().unwrap_or_default()
+                )),
+                Some(_) => None,
+            };
+
+            if let Some(reason) = reason {
+                mismatches.push(FailedFile {
+                    path: file.path.clone(),
+                    error: format!("verification failed: {reason}"),
+                });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    fn write_dry_run(
         &self,
-        res: UploadResponse,
+        report: &Report,
         mut writer: impl std::io::Write,
     ) -> Result<(), NeocitiesErr> {
+        self.write(
+            "\x1b[93mDry run\x1b[0m: nothing will be sent. The upload would contain:\n",
+            &mut writer,
+        )?;
+
+        for file in report.files.iter() {
+            let output = format!(
+                "  \x1b[1;92m{}\x1b[0m ({} bytes) {}\n",
+                file.path,
+                file.size.unwrap_or_default(),
+                file.status.as_deref().unwrap_or_default()
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
+
         let output = format!(
-            "\x1b[93mStatus\x1b[0m: {} - {}\n",
-            &res.result, &res.message
+            "Total: {} file(s), {} bytes\n",
+            report.files.len(),
+            report.total_bytes
         );
-        writer.write_all(output.as_bytes())?;
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(())
+    }
+
+    /// Records what happened to the files of a batch: the files that landed are added to the
+    /// journal, the session is saved, and a line of progress is written. It returns the outcome.
+    fn record_batch(
+        &self,
+        label: &str,
+        outcome: BatchOutcome,
+        base: &JournalEntry,
+        snapshots: &HashMap<String, JournalFile>,
+        session: &mut UploadSession,
+        mut writer: impl std::io::Write,
+    ) -> Result<BatchOutcome, NeocitiesErr> {
+        let landed: Vec<JournalFile> = outcome
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .filter_map(|(path, _)| snapshots.get(path).cloned())
+            .collect();
+        if let Some((_, Ok(message))) = outcome.iter().find(|(_, r)| r.is_ok()) {
+            JournalEntry {
+                timestamp: Utc::now().to_rfc3339(),
+                files: landed,
+                ..base.clone()
+            }
+            .respond("success", None, message)
+            .append()?;
+        }
+
+        session.record(&outcome);
+        session.write()?;
+
+        let failed = outcome.iter().filter(|(_, r)| r.is_err()).count();
+        let output = format!(
+            "{label}: {} uploaded, {} failed\n",
+            outcome.len() - failed,
+            failed
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        Ok(outcome)
+    }
+
+    /// Uploads the pending files of the session in batches, with up to `jobs` batches in flight
+    /// at once. A batch that fails is sent again file by file, so that a failure can be traced to
+    /// the files that caused it. The progress is recorded in the session as each batch arrives,
+    /// and each uploaded file in the journal under the given command. It returns what happened to
+    /// every file.
+    pub(crate) fn upload_batches(
+        &self,
+        command: &str,
+        batch_size: usize,
+        jobs: usize,
+        session: &mut UploadSession,
+        send: impl Fn(
+            Vec<Vec<String>>,
+            usize,
+            &mut dyn FnMut(usize, Result<UploadResponse, NeocitiesErr>),
+        ) -> Result<(), NeocitiesErr>,
+        mut writer: impl std::io::Write,
+    ) -> Result<BatchOutcome, NeocitiesErr> {
+        let paths = session.pending();
+        let batches: Vec<Vec<String>> = paths.chunks(batch_size).map(|b| b.to_vec()).collect();
+
+        let mut snapshots: HashMap<String, JournalFile> = HashMap::new();
+        for path in paths.iter() {
+            snapshots.insert(path.clone(), objects::snapshot(path)?);
+        }
+
+        // the site and user are looked up once, since no other request may be sent while the
+        // batches are in flight
+        let base = JournalEntry::new("upload", command, trash::sitename().ok(), Vec::new());
+
+        let mut outcomes: BatchOutcome = Vec::new();
+        let mut retries: Vec<Vec<String>> = Vec::new();
+        let mut error: Option<NeocitiesErr> = None;
+        let count = batches.len();
+
+        send(batches.clone(), jobs, &mut |i, res| {
+            let batch = &batches[i];
+            let label = format!("Batch {}/{}", i + 1, count);
+
+            let recorded = match attempt_result(res) {
+                Err(_) if batch.len() > 1 => {
+                    retries.extend(batch.iter().map(|p| vec![p.clone()]));
+                    let output = format!("{label}: failed, retrying its files one by one\n");
+                    self.write(output.as_str(), &mut writer)
+                }
+                result => {
+                    let outcome = batch.iter().map(|p| (p.clone(), result.clone())).collect();
+                    self.record_batch(&label, outcome, &base, &snapshots, session, &mut writer)
+                        .map(|o| outcomes.extend(o))
+                }
+            };
+
+            if let Err(e) = recorded {
+                error.get_or_insert(e);
+            }
+        })?;
+
+        if !retries.is_empty() {
+            send(retries.clone(), jobs, &mut |i, res| {
+                let path = retries[i][0].clone();
+                let label = format!("Retry {path}");
+                let outcome = vec![(path, attempt_result(res))];
+
+                let recorded = self
+                    .record_batch(&label, outcome, &base, &snapshots, session, &mut writer)
+                    .map(|o| outcomes.extend(o));
+                if let Err(e) = recorded {
+                    error.get_or_insert(e);
+                }
+            })?;
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(outcomes),
+        }
+    }
+
+    pub(crate) fn write_failures(
+        &self,
+        session: &UploadSession,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        for failure in session.failed.iter() {
+            let output = format!(
+                "  \x1b[91mfailed\x1b[0m {}: {}\n",
+                failure.path, failure.error
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
         Ok(())
     }
 }
 
+/// Returns the server's message if a request uploaded its files, or the reason it did not.
+pub(crate) fn attempt_result(res: Result<UploadResponse, NeocitiesErr>) -> Result<String, String> {
+    match res {
+        Ok(res) if res.result == "success" => Ok(res.message),
+        Ok(res) => Err(match res.error_type {
+            Some(t) => format!("{t}: {}", res.message),
+            None => res.message,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 impl Executable for Upload {
     fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
         let mut stdout = std::io::stdout();
@@ -66,9 +741,174 @@ impl Executable for Upload {
             return Ok(());
         }
 
-        let data = NcUpload::fetch(args)?;
-        self.parse_response(data, &mut stdout)?;
+        let (opts, args) = self.parse_opts(args)?;
+        if args.is_empty() == opts.resume {
+            return Err(NeocitiesErr::InvalidArgument);
+        }
+
+        let mut session = match opts.resume {
+            true => UploadSession::read()?,
+            false => UploadSession::new(self.expand_paths(&args)?),
+        };
+
+        let limits = Limits::new(
+            opts.supporter || filetype::is_supporter(),
+            opts.storage_limit,
+            opts.max_file_size,
+        )?;
+
+        // every file is checked before any is sent, so that a rejected type cannot fail a batch
+        // halfway through the upload
+        let rejected = self.validate(
+            &session.pending(),
+            opts.supporter || filetype::is_supporter(),
+            limits.max_file_size,
+        )?;
+        if !rejected.is_empty() {
+            self.write_rejected(&rejected, opts.format, &mut stdout)?;
+            return Err(NeocitiesErr::DisallowedFileType);
+        }
+
+        if opts.dry_run {
+            // resolve the credentials the request would use, without sending it
+            NcUpload::request_info(session.pending())?;
+        }
+
+        let list = NcList::fetch(None)?;
+
+        // a resumed upload skips the files that landed before it was interrupted, and
+        // `--changed-only` the files that are already live
+        let mut skipped: Vec<String> = Vec::new();
+        let mut paths = session.pending();
+        if opts.resume || opts.changed_only {
+            skipped = self.unchanged(&paths, &list)?;
+            paths.retain(|p| !skipped.contains(p));
+        }
+
+        self.check_quota(&list, &paths, &limits, std::io::stderr())?;
+
+        if opts.dry_run {
+            let reports = self.dry_run_reports(&session.pending(), &list, &skipped)?;
+            let report = Report::new(KEY, true, reports);
+
+            match opts.format {
+                Format::Json => report.write_json(&mut stdout)?,
+                _ => self.write_dry_run(&report, &mut stdout)?,
+            }
+            return Ok(());
+        }
+
+        session.skip(&skipped);
+
+        let sizes = paths
+            .iter()
+            .map(|p| Ok((p.clone(), fs::metadata(p)?.len())))
+            .collect::<Result<Vec<(String, u64)>, NeocitiesErr>>()?;
+        let style = match opts.format {
+            Format::Json => Style::Json,
+            _ if std::io::stderr().is_terminal() => Style::Bar,
+            _ => Style::Silent,
+        };
+        let progress = Arc::new(Progress::new(style, &sizes));
+
+        let tracker = progress.clone();
+        let transfer = Arc::new(Transfer::new(opts.limit_rate, move |path, sent| {
+            tracker.update(path, sent)
+        }));
+        let send = |batches, jobs, on_done: &mut dyn FnMut(usize, _)| {
+            NcUpload::fetch_all(batches, jobs, Some(transfer.clone()), on_done)
+        };
+
+        // in JSON mode, stderr carries only the progress events and stdout only the report
+        let outcomes = match opts.format {
+            Format::Json => self.upload_batches(
+                KEY,
+                opts.batch_size,
+                opts.jobs,
+                &mut session,
+                send,
+                std::io::sink(),
+            )?,
+            _ => self.upload_batches(
+                KEY,
+                opts.batch_size,
+                opts.jobs,
+                &mut session,
+                send,
+                &*progress,
+            )?,
+        };
+        progress.finish()?;
+
+        let failed = outcomes.iter().filter(|(_, r)| r.is_err()).count();
+        let mut summary = format!(
+            "{} uploaded, {} skipped, {} failed",
+            outcomes.len() - failed,
+            skipped.len(),
+            failed
+        );
+
+        // a file that does not match the remote is left for `--resume` to send again
+        let mut mismatches: Vec<FailedFile> = Vec::new();
+        if opts.verify {
+            let uploaded: Vec<String> = outcomes
+                .iter()
+                .filter(|(_, r)| r.is_ok())
+                .map(|(p, _)| p.clone())
+                .collect();
+            let list = NcList::fetch(None)?;
+            mismatches = self.verify(&self.file_reports(&uploaded, None)?, &list)?;
+
+            for m in mismatches.iter() {
+                session.uploaded.retain(|p| *p != m.path);
+                session.failed.push(m.clone());
+            }
+            session.write()?;
+
+            summary = format!("{summary}, {} mismatched", mismatches.len());
+        }
+
+        match opts.format {
+            Format::Json => {
+                let mut reports = self.file_reports(&session.files, None)?;
+                for report in reports.iter_mut() {
+                    if skipped.contains(&report.path) {
+                        report.status = Some(String::from("skipped"));
+                    } else if let Some(m) = mismatches.iter().find(|m| m.path == report.path) {
+                        report.status = Some(String::from("mismatch"));
+                        report.message = Some(m.error.clone());
+                    } else if let Some(f) = session.failed.iter().find(|f| f.path == report.path) {
+                        report.status = Some(String::from("failed"));
+                        report.message = Some(f.error.clone());
+                    } else if session.uploaded.contains(&report.path) {
+                        report.status = Some(String::from("uploaded"));
+                    }
+                }
+
+                let mut report = Report::new(KEY, false, reports);
+                report.result = Some(String::from(match session.failed.len() {
+                    0 => "success",
+                    _ => "error",
+                }));
+                report.message = Some(summary);
+                report.write_json(&mut stdout)?;
+            }
+            _ => {
+                let output = format!("\x1b[93mStatus\x1b[0m: {summary}\n");
+                self.write(output.as_str(), &mut stdout)?;
+                self.write_failures(&session, &mut stdout)?;
+            }
+        }
+
+        if failed > 0 {
+            return Err(NeocitiesErr::UploadFailed);
+        }
+
+        if !mismatches.is_empty() {
+            return Err(NeocitiesErr::VerifyFailed);
+        }
 
+        UploadSession::remove()?;
         Ok(())
     }
 
@@ -87,12 +927,31 @@ impl Executable for Upload {
 
 const DESC_SHORT: &'static str = "Upload files to Neocities";
 
-const DESC: &'static str = "Upload files to your Neocities website";
+const DESC: &'static str = "Upload files, directories or quoted glob patterns to your Neocities \
+website. Every file is checked against the types, sizes and storage Neocities allows before \
+anything is sent, and `upload --resume` continues an interrupted or partly failed upload.";
 
 #[cfg(test)]
 mod tests {
-    use super::{Upload, DESC, DESC_SHORT, KEY};
-    use crate::{api::upload::UploadResponse, client::command::Executable, error::NeocitiesErr};
+    use super::{
+        attempt_result, FailedFile, Upload, UploadSession, DEFAULT_BATCH_SIZE, DESC, DESC_SHORT,
+        KEY,
+    };
+    use crate::{
+        api::{
+            list::{File, ListResponse},
+            upload::UploadResponse,
+        },
+        client::{
+            command::Executable,
+            journal::JournalEntry,
+            output::{FileReport, Format, Report},
+            state::TempStateDir,
+            usage::{Limits, DEFAULT_MAX_FILE_SIZE},
+        },
+        error::NeocitiesErr,
+    };
+    use serial_test::serial;
 
     #[test]
     fn get_usage_method() {
@@ -125,22 +984,426 @@ mod tests {
         Ok(())
     }
 
+    fn response(result: &str, message: &str) -> UploadResponse {
+        UploadResponse {
+            result: String::from(result),
+            error_type: match result {
+                "success" => None,
+                _ => Some(String::from("invalid_file_type")),
+            },
+            message: String::from(message),
+        }
+    }
+
+    /// Answers each batch with `respond`, in reverse order, as batches sent at once may arrive in
+    /// any order
+    fn send_all(
+        respond: impl Fn(&[String]) -> Result<UploadResponse, NeocitiesErr>,
+    ) -> impl Fn(
+        Vec<Vec<String>>,
+        usize,
+        &mut dyn FnMut(usize, Result<UploadResponse, NeocitiesErr>),
+    ) -> Result<(), NeocitiesErr> {
+        move |batches, _, on_done| {
+            for (i, batch) in batches.iter().enumerate().rev() {
+                on_done(i, respond(batch));
+            }
+            Ok(())
+        }
+    }
+
     #[test]
-    fn parse_response_method() -> Result<(), NeocitiesErr> {
-        let mock_res = UploadResponse {
-            result: String::from("foo"),
-            error_type: None,
-            message: String::from("bar"),
-        };
+    fn attempt_result_function() {
+        assert_eq!(
+            attempt_result(Ok(response("success", "uploaded"))),
+            Ok(String::from("uploaded"))
+        );
+        assert_eq!(
+            attempt_result(Ok(response("error", "b.exe is not a valid file type"))),
+            Err(String::from(
+                "invalid_file_type: b.exe is not a valid file type"
+            ))
+        );
+        // an error without a type is reported by its message alone
+        assert_eq!(
+            attempt_result(Ok(UploadResponse {
+                result: String::from("error"),
+                error_type: None,
+                message: String::from("something went wrong"),
+            })),
+            Err(String::from("something went wrong"))
+        );
+        assert_eq!(
+            attempt_result(Err(NeocitiesErr::InvalidArgument)),
+            Err(String::from("invalid argument"))
+        );
+    }
+
+    #[test]
+    #[serial(env)]
+    fn upload_batches_method_retries() -> Result<(), NeocitiesErr> {
+        let _state_dir = TempStateDir::new()?;
 
         let u = Upload::new();
+        let paths =
+            to_args("tests/fixtures/bar.js tests/fixtures/foo.html tests/fixtures/images/baz.jpg");
+        let mut session = UploadSession::new(paths.clone());
+
+        // a failed batch is retried file by file, so the failure is traced to the file
         let mut output = Vec::new();
-        u.parse_response(mock_res, &mut output)?;
+        let outcomes = u.upload_batches(
+            KEY,
+            2,
+            4,
+            &mut session,
+            send_all(|batch| match batch.iter().any(|p| p.ends_with(".js")) {
+                true => Ok(response("error", "bar.js is not a valid file type")),
+                false => Ok(response("success", "uploaded")),
+            }),
+            &mut output,
+        )?;
+        assert_eq!(outcomes.len(), 3);
 
         let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("Batch 2/2: 1 uploaded, 0 failed"), true);
+        assert_eq!(s.contains("Batch 1/2: failed, retrying"), true);
+        assert_eq!(
+            s.contains("Retry tests/fixtures/foo.html: 1 uploaded, 0 failed"),
+            true
+        );
+        assert_eq!(
+            s.contains("Retry tests/fixtures/bar.js: 0 uploaded, 1 failed"),
+            true
+        );
 
-        assert_eq!(s.contains("foo"), true);
-        assert_eq!(s.contains("bar"), true);
+        assert_eq!(session.pending(), to_args("tests/fixtures/bar.js"));
+        assert_eq!(
+            session.failed[0].error,
+            "invalid_file_type: bar.js is not a valid file type"
+        );
+        assert_eq!(JournalEntry::read_all()?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial(env)]
+    fn upload_batches_method() -> Result<(), NeocitiesErr> {
+        let _state_dir = TempStateDir::new()?;
+
+        let u = Upload::new();
+        let paths =
+            to_args("tests/fixtures/bar.js tests/fixtures/foo.html tests/fixtures/images/baz.jpg");
+        let mut session = UploadSession::new(paths.clone());
+
+        let mut output = Vec::new();
+        let outcomes = u.upload_batches(
+            KEY,
+            2,
+            1,
+            &mut session,
+            send_all(|batch| match batch.iter().any(|p| p.ends_with(".jpg")) {
+                true => Err(NeocitiesErr::InvalidArgument),
+                false => Ok(response("success", "uploaded")),
+            }),
+            &mut output,
+        )?;
+        assert_eq!(outcomes.len(), 3);
+
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("Batch 1/2: 2 uploaded, 0 failed"), true);
+        assert_eq!(s.contains("Batch 2/2: 0 uploaded, 1 failed"), true);
+
+        // the progress is saved, and only the failed file is left to resume
+        let saved = UploadSession::read()?;
+        assert_eq!(saved, session);
+        assert_eq!(saved.pending(), to_args("tests/fixtures/images/baz.jpg"));
+        assert_eq!(saved.failed[0].error, "invalid argument");
+
+        // each batch that landed is in the journal
+        let entries = JournalEntry::read_all()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].files.len(), 2);
+
+        let mut output = Vec::new();
+        u.write_failures(&saved, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?.contains("baz.jpg: invalid argument"),
+            true
+        );
+
+        UploadSession::remove()?;
+        assert_eq!(
+            matches!(UploadSession::read(), Err(NeocitiesErr::NoUploadToResume)),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_method_resume() {
+        let paths = to_args("tests/fixtures/foo.html tests/fixtures/images/baz.jpg");
+        let mut session = UploadSession::new(paths.clone());
+        session.record(&vec![
+            (paths[0].clone(), Ok(String::from("uploaded"))),
+            (paths[1].clone(), Err(String::from("invalid argument"))),
+        ]);
+        assert_eq!(session.pending(), to_args("tests/fixtures/images/baz.jpg"));
+
+        // the failed file already matches the remote by the time the upload is resumed
+        let skipped = session.pending();
+        session.skip(&skipped);
+        assert_eq!(session.pending().is_empty(), true);
+        assert_eq!(session.failed.is_empty(), true);
+        assert_eq!(session.uploaded, paths);
+    }
+
+    fn to_args(s: &str) -> Vec<String> {
+        s.split(' ').map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+
+        let (opts, rest) = u.parse_opts(to_args("--dry-run foo.html --output json bar.js"))?;
+        assert_eq!(opts.dry_run, true);
+        assert_eq!(opts.format, Format::Json);
+        assert_eq!(rest, to_args("foo.html bar.js"));
+
+        let (opts, rest) = u.parse_opts(to_args("foo.html"))?;
+        assert_eq!(opts.dry_run, false);
+        assert_eq!(opts.format, Format::Text);
+        assert_eq!(opts.resume, false);
+        assert_eq!(opts.batch_size, DEFAULT_BATCH_SIZE);
+        assert_eq!(opts.jobs, 1);
+        assert_eq!(rest.len(), 1);
+
+        let (opts, rest) = u.parse_opts(to_args("--resume --batch-size 10"))?;
+        assert_eq!(opts.resume, true);
+        assert_eq!(opts.changed_only, false);
+        assert_eq!(opts.verify, false);
+        assert_eq!(opts.supporter, false);
+        assert_eq!(opts.storage_limit, None);
+        assert_eq!(opts.batch_size, 10);
+        assert_eq!(rest.is_empty(), true);
+
+        let (opts, _) =
+            u.parse_opts(to_args("-j 8 --changed-only --verify --supporter foo.html"))?;
+        assert_eq!(opts.supporter, true);
+        assert_eq!(opts.verify, true);
+        assert_eq!(opts.jobs, 8);
+        assert_eq!(opts.changed_only, true);
+        assert_eq!(opts.limit_rate, None);
+
+        let (opts, _) = u.parse_opts(to_args("--limit-rate 500K foo.html"))?;
+        assert_eq!(opts.limit_rate, Some(500 * 1024));
+        assert_eq!(u.parse_opts(to_args("--limit-rate fast")).is_err(), true);
+
+        let (opts, _) = u.parse_opts(to_args("--storage-limit 5G --max-file-size 10M a.html"))?;
+        assert_eq!(opts.storage_limit, Some(5 * 1024 * 1024 * 1024));
+        assert_eq!(opts.max_file_size, Some(10 * 1024 * 1024));
+
+        assert_eq!(u.parse_opts(to_args("--batch-size 0")).is_err(), true);
+        assert_eq!(u.parse_opts(to_args("--jobs 0")).is_err(), true);
+        assert_eq!(u.parse_opts(to_args("--batch-size")).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_paths_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+
+        let files = u.expand_paths(&to_args("tests/fixtures"))?;
+        assert_eq!(
+            files,
+            to_args("tests/fixtures/bar.js tests/fixtures/foo.html tests/fixtures/images/baz.jpg")
+        );
+
+        let files = u.expand_paths(&to_args("tests/fixtures/*.html tests/fixtures/foo.html"))?;
+        assert_eq!(files, to_args("tests/fixtures/foo.html"));
+
+        assert_eq!(u.expand_paths(&to_args("nonexistent.html")).is_err(), true);
+        assert_eq!(u.expand_paths(&to_args("tests/*.nothing")).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+        let temp_dir = tempfile::tempdir()?;
+        let exe = temp_dir.path().join("setup.exe");
+        std::fs::write(&exe, "MZ")?;
+        let exe = exe.to_str().unwrap().to_string();
+
+        let mut paths = to_args("tests/fixtures/foo.html tests/fixtures/images/baz.jpg");
+        assert_eq!(
+            u.validate(&paths, false, DEFAULT_MAX_FILE_SIZE)?.is_empty(),
+            true
+        );
+
+        paths.push(exe.clone());
+        let rejected = u.validate(&paths, false, DEFAULT_MAX_FILE_SIZE)?;
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].path, exe);
+        assert_eq!(
+            u.validate(&paths, true, DEFAULT_MAX_FILE_SIZE)?.is_empty(),
+            true
+        );
+
+        let too_big = u.validate(&paths[..1], false, 10)?;
+        assert_eq!(
+            too_big[0].error.contains("over the limit of 10 B per file"),
+            true
+        );
+
+        let mut output = Vec::new();
+        u.write_rejected(&rejected, Format::Text, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("rejected"), true);
+        assert_eq!(
+            s.contains("setup.exe: `.exe` files need a supporter plan"),
+            true
+        );
+
+        let mut output = Vec::new();
+        u.write_rejected(&rejected, Format::Json, &mut output)?;
+        let v: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(v["files"][0]["status"], "rejected");
+        assert_eq!(v["result"], "error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_quota_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+        let paths = to_args("tests/fixtures/foo.html");
+        let size = std::fs::metadata("tests/fixtures/foo.html")?.len();
+        let remote = ListResponse {
+            result: String::from("success"),
+            files: vec![File {
+                path: String::from("other.html"),
+                is_directory: false,
+                size: Some(100),
+                updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+                sha1_hash: None,
+            }],
+        };
+        let limits = |storage| Limits {
+            storage,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        };
+
+        let mut output = Vec::new();
+        u.check_quota(&remote, &paths, &limits(10 * (100 + size)), &mut output)?;
+        assert_eq!(output.is_empty(), true);
+
+        let mut output = Vec::new();
+        u.check_quota(&remote, &paths, &limits(100 + size), &mut output)?;
+        assert_eq!(String::from_utf8(output)?.contains("Warning"), true);
+
+        let mut output = Vec::new();
+        let res = u.check_quota(&remote, &paths, &limits(100 + size - 1), &mut output);
+        assert_eq!(matches!(res, Err(NeocitiesErr::QuotaExceeded)), true);
+        assert_eq!(String::from_utf8(output)?.contains("Over quota"), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+        let paths =
+            to_args("tests/fixtures/foo.html tests/fixtures/bar.js tests/fixtures/images/baz.jpg");
+        let expected = u.file_reports(&paths, None)?;
+
+        let remote_file = |report: &FileReport| File {
+            path: report.path.clone(),
+            is_directory: false,
+            size: report.size,
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: report.sha1_hash.clone(),
+        };
+
+        let mut truncated = remote_file(&expected[1]);
+        truncated.size = Some(1);
+        let mut corrupted = remote_file(&expected[0]);
+        corrupted.sha1_hash = Some(String::from("abc"));
+
+        let remote = ListResponse {
+            result: String::from("success"),
+            files: expected.iter().map(remote_file).collect(),
+        };
+        assert_eq!(u.verify(&expected, &remote)?.is_empty(), true);
+
+        let remote = ListResponse {
+            result: String::from("success"),
+            files: vec![corrupted, truncated],
+        };
+        let mismatches = u.verify(&expected, &remote)?;
+        assert_eq!(mismatches.len(), 3);
+        assert_eq!(mismatches[0].error.contains("SHA-1 abc"), true);
+        assert_eq!(mismatches[1].error.contains("has 1 bytes"), true);
+        assert_eq!(
+            mismatches[2],
+            FailedFile {
+                path: String::from("tests/fixtures/images/baz.jpg"),
+                error: String::from("verification failed: missing from your site"),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_reports_method() -> Result<(), NeocitiesErr> {
+        let u = Upload::new();
+        let paths = to_args("tests/fixtures/foo.html tests/fixtures/bar.js");
+
+        let reports = u.file_reports(&paths, None)?;
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].status, None);
+        assert_eq!(
+            reports[0].size,
+            Some(std::fs::metadata("tests/fixtures/foo.html")?.len() as i64)
+        );
+
+        let remote = ListResponse {
+            result: String::from("success"),
+            files: vec![File {
+                path: String::from("tests/fixtures/foo.html"),
+                is_directory: false,
+                size: Some(1),
+                updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+                sha1_hash: reports[0].sha1_hash.clone(),
+            }],
+        };
+
+        let reports = u.file_reports(&paths, Some(&remote))?;
+        assert_eq!(reports[0].status.as_deref(), Some("unchanged"));
+        assert_eq!(reports[1].status.as_deref(), Some("new"));
+        assert_eq!(
+            u.unchanged(&paths, &remote)?,
+            to_args("tests/fixtures/foo.html")
+        );
+
+        // a dry run keeps the files a real upload would skip, marked as skipped
+        let skipped = u.unchanged(&paths, &remote)?;
+        let dry_run = u.dry_run_reports(&paths, &remote, &skipped)?;
+        assert_eq!(dry_run.len(), 2);
+        assert_eq!(dry_run[0].status.as_deref(), Some("skipped"));
+        assert_eq!(dry_run[1].status.as_deref(), Some("new"));
+
+        let mut output = Vec::new();
+        u.write_dry_run(&Report::new(KEY, true, reports), &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("Dry run"), true);
+        assert_eq!(s.contains("tests/fixtures/bar.js"), true);
+        assert_eq!(s.contains("Total: 2 file(s)"), true);
 
         Ok(())
     }
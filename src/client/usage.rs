@@ -0,0 +1,455 @@
+use super::{
+    command::Executable,
+    filetype,
+    output::{format_bytes, parse_bytes, Format},
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        list::{ListResponse, NcList},
+    },
+    error::NeocitiesErr,
+};
+use serde_derive::Serialize;
+use std::{collections::BTreeMap, env};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "usage";
+
+/// The environment variable that overrides the storage limit of the account, e.g. `5G`
+pub const ENV_STORAGE_LIMIT: &str = "NEOCITIES_STORAGE_LIMIT";
+
+/// The environment variable that overrides the largest file the account may upload, e.g. `50M`
+pub const ENV_MAX_FILE_SIZE: &str = "NEOCITIES_MAX_FILE_SIZE";
+
+/// The storage limit of a free Neocities account
+pub const FREE_STORAGE_LIMIT: u64 = 1024 * 1024 * 1024;
+
+/// The storage limit of a Neocities supporter account
+pub const SUPPORTER_STORAGE_LIMIT: u64 = 50 * 1024 * 1024 * 1024;
+
+/// The largest single file Neocities accepts
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// The share of the storage limit past which an upload is allowed, but warned about
+pub const WARN_RATIO: f64 = 0.9;
+
+/// Shows how much of the account's storage a Neocities website uses, broken down by directory.
+pub struct Usage {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The storage limits that apply to an account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// The most bytes the website may hold
+    pub storage: u64,
+    /// The most bytes a single file may hold
+    pub max_file_size: u64,
+}
+
+impl Limits {
+    /// Returns the limits of the account's plan. A limit given on the command line takes
+    /// precedence over one set in the environment, which takes precedence over the Neocities
+    /// default.
+    pub fn new(
+        supporter: bool,
+        storage: Option<u64>,
+        max_file_size: Option<u64>,
+    ) -> Result<Limits, NeocitiesErr> {
+        let storage = match (storage, env::var(ENV_STORAGE_LIMIT)) {
+            (Some(s), _) => s,
+            (None, Ok(v)) => parse_bytes(&v)?,
+            (None, Err(_)) if supporter => SUPPORTER_STORAGE_LIMIT,
+            (None, Err(_)) => FREE_STORAGE_LIMIT,
+        };
+
+        let max_file_size = match (max_file_size, env::var(ENV_MAX_FILE_SIZE)) {
+            (Some(s), _) => s,
+            (None, Ok(v)) => parse_bytes(&v)?,
+            (None, Err(_)) => DEFAULT_MAX_FILE_SIZE,
+        };
+
+        Ok(Limits {
+            storage,
+            max_file_size,
+        })
+    }
+}
+
+/// The space used by the files under a directory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirUsage {
+    /// The directory, or an empty string for the files at the root of the website
+    pub path: String,
+    /// The total byte size of the files
+    pub size: u64,
+    /// The number of files
+    pub files: usize,
+}
+
+/// The JSON document written by `usage --output json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct UsageReport {
+    used: u64,
+    limit: u64,
+    free: u64,
+    directories: Vec<DirUsage>,
+}
+
+/// Returns the number of bytes the files of a website take up.
+pub fn used(list: &ListResponse) -> u64 {
+    list.files
+        .iter()
+        .filter(|f| !f.is_directory)
+        .map(|f| u64::try_from(f.size.unwrap_or_default()).unwrap_or_default())
+        .sum()
+}
+
+/// Returns the number of bytes the website would take up once the given files, as pairs of a
+/// remote path and a byte size, are uploaded. A file replaced by an upload no longer counts.
+pub fn projected(list: &ListResponse, uploads: &[(String, u64)]) -> u64 {
+    let replaced: u64 = list
+        .files
+        .iter()
+        .filter(|f| !f.is_directory && uploads.iter().any(|(p, _)| *p == f.path))
+        .map(|f| u64::try_from(f.size.unwrap_or_default()).unwrap_or_default())
+        .sum();
+    let added: u64 = uploads.iter().map(|(_, size)| size).sum();
+
+    used(list) - replaced + added
+}
+
+/// Returns the space used under each top-level directory, largest first.
+pub fn by_directory(list: &ListResponse) -> Vec<DirUsage> {
+    let mut dirs: BTreeMap<String, DirUsage> = BTreeMap::new();
+
+    for file in list.files.iter().filter(|f| !f.is_directory) {
+        let dir = match file.path.split_once('/') {
+            Some((top, _)) => top.to_string(),
+            None => String::new(),
+        };
+
+        let entry = dirs.entry(dir.clone()).or_insert(DirUsage {
+            path: dir,
+            size: 0,
+            files: 0,
+        });
+        entry.size += u64::try_from(file.size.unwrap_or_default()).unwrap_or_default();
+        entry.files += 1;
+    }
+
+    let mut dirs: Vec<DirUsage> = dirs.into_values().collect();
+    dirs.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+    dirs
+}
+
+impl Usage {
+    /// A constructor that returns an instance of `Usage`.
+    pub fn new() -> Usage {
+        Usage {
+            usage: format!(
+                "\x1b[1;32m{KEY}\x1b[0m [--supporter] [--storage-limit <size>] [--output <text|porcelain|json>]"
+            ),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<(Limits, Format), NeocitiesErr> {
+        let mut format = Format::Text;
+        let mut supporter = filetype::is_supporter();
+        let mut storage: Option<u64> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            if let Some((f, consumed)) = Format::from_args(&args[i..])? {
+                format = f;
+                i += consumed;
+                continue;
+            }
+
+            match args[i].as_str() {
+                "--supporter" => supporter = true,
+                "--storage-limit" => {
+                    storage = match args.get(i + 1) {
+                        Some(v) => Some(parse_bytes(v)?),
+                        None => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 1;
+        }
+
+        Ok((Limits::new(supporter, storage, None)?, format))
+    }
+
+    fn write_text(
+        &self,
+        list: &ListResponse,
+        limits: &Limits,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let used = used(list);
+        let percent = used as f64 / limits.storage.max(1) as f64 * 100.0;
+
+        let output = format!(
+            "\x1b[93mUsed\x1b[0m: {} of {} ({percent:.1}%)\n\x1b[93mFree\x1b[0m: {}\n\n",
+            format_bytes(used),
+            format_bytes(limits.storage),
+            format_bytes(limits.storage.saturating_sub(used))
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        for dir in by_directory(list).iter() {
+            let name = match dir.path.as_str() {
+                "" => String::from("(root)"),
+                p => format!("{p}/"),
+            };
+            let output = format!(
+                "  {:>10}  \x1b[1;94m{name}\x1b[0m ({} files)\n",
+                format_bytes(dir.size),
+                dir.files
+            );
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the used, limit and free bytes on one line, then one line per top-level directory
+    /// with its bytes, number of files and path, separated by tabs.
+    fn write_porcelain(
+        &self,
+        list: &ListResponse,
+        limits: &Limits,
+        mut writer: impl std::io::Write,
+    ) -> Result<(), NeocitiesErr> {
+        let used = used(list);
+        let output = format!(
+            "{used}\t{}\t{}\n",
+            limits.storage,
+            limits.storage.saturating_sub(used)
+        );
+        self.write(output.as_str(), &mut writer)?;
+
+        for dir in by_directory(list).iter() {
+            let output = format!("{}\t{}\t{}\n", dir.size, dir.files, dir.path);
+            self.write(output.as_str(), &mut writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Usage {
+    fn default() -> Usage {
+        Usage::new()
+    }
+}
+
+impl Executable for Usage {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let (limits, format) = self.parse_opts(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let list = NcList::fetch(None)?;
+
+        match format {
+            Format::Json => {
+                let used = used(&list);
+                let report = UsageReport {
+                    used,
+                    limit: limits.storage,
+                    free: limits.storage.saturating_sub(used),
+                    directories: by_directory(&list),
+                };
+                let output = format!("{}\n", serde_json::to_string_pretty(&report)?);
+                self.write(output.as_str(), &mut stdout)?;
+            }
+            Format::Porcelain => self.write_porcelain(&list, &limits, &mut stdout)?,
+            Format::Text => self.write_text(&list, &limits, &mut stdout)?,
+        }
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Show how much of your storage your Neocities website uses and how much is \
+free, with a breakdown by top-level directory. The limit is 1 GiB, or 50 GiB with `--supporter` \
+(or NEOCITIES_SUPPORTER=1); set another with `--storage-limit` or NEOCITIES_STORAGE_LIMIT (e.g. \
+5G). Use `--output json` or `--output porcelain` for scripts.";
+
+const DESC_SHORT: &str = "Show disk usage and free space";
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        by_directory, projected, used, Limits, Usage, DEFAULT_MAX_FILE_SIZE, DESC, DESC_SHORT,
+        ENV_STORAGE_LIMIT, FREE_STORAGE_LIMIT, KEY, SUPPORTER_STORAGE_LIMIT,
+    };
+    use crate::{
+        api::list::{File, ListResponse},
+        client::{command::Executable, filetype::ENV_SUPPORTER, output::Format},
+        error::NeocitiesErr,
+    };
+    use serial_test::serial;
+    use std::env;
+
+    #[test]
+    fn usage_desc_methods() {
+        let u = Usage::new();
+        assert_eq!(u.get_usage().contains(KEY), true);
+        assert_eq!(u.get_short_desc(), DESC_SHORT);
+        assert_eq!(u.get_long_desc(), DESC);
+    }
+
+    fn file(path: &str, size: Option<i64>) -> File {
+        File {
+            path: path.to_string(),
+            is_directory: size.is_none(),
+            size,
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: None,
+        }
+    }
+
+    fn list() -> ListResponse {
+        ListResponse {
+            result: String::from("success"),
+            files: vec![
+                file("index.html", Some(100)),
+                file("images", None),
+                file("images/a.png", Some(2000)),
+                file("images/icons/b.png", Some(1000)),
+                file("css", None),
+                file("css/style.css", Some(300)),
+            ],
+        }
+    }
+
+    #[test]
+    fn used_and_projected_functions() {
+        let l = list();
+        assert_eq!(used(&l), 3400);
+
+        let uploads = vec![
+            (String::from("index.html"), 150),
+            (String::from("about.html"), 50),
+        ];
+        assert_eq!(projected(&l, &uploads), 3500);
+    }
+
+    #[test]
+    fn by_directory_function() {
+        let dirs = by_directory(&list());
+        let summary: Vec<(&str, u64, usize)> = dirs
+            .iter()
+            .map(|d| (d.path.as_str(), d.size, d.files))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("images", 3000, 2), ("css", 300, 1), ("", 100, 1)]
+        );
+    }
+
+    #[test]
+    #[serial(env)]
+    fn limits_new_method() -> Result<(), NeocitiesErr> {
+        let prev_limit = env::var(ENV_STORAGE_LIMIT);
+        env::remove_var(ENV_STORAGE_LIMIT);
+
+        let limits = Limits::new(false, None, None)?;
+        assert_eq!(limits.storage, FREE_STORAGE_LIMIT);
+        assert_eq!(limits.max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(
+            Limits::new(true, None, None)?.storage,
+            SUPPORTER_STORAGE_LIMIT
+        );
+
+        env::set_var(ENV_STORAGE_LIMIT, "5G");
+        assert_eq!(
+            Limits::new(false, None, None)?.storage,
+            5 * 1024 * 1024 * 1024
+        );
+        assert_eq!(Limits::new(false, Some(10), Some(5))?.storage, 10);
+
+        match prev_limit {
+            Ok(p) => env::set_var(ENV_STORAGE_LIMIT, p),
+            Err(_) => env::remove_var(ENV_STORAGE_LIMIT),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial(env)]
+    fn parse_opts_and_write_methods() -> Result<(), NeocitiesErr> {
+        let prev_limit = env::var(ENV_STORAGE_LIMIT);
+        let prev_supporter = env::var(ENV_SUPPORTER);
+        env::remove_var(ENV_STORAGE_LIMIT);
+        env::remove_var(ENV_SUPPORTER);
+
+        let u = Usage::new();
+        let (limits, format) = u.parse_opts(vec![String::from("--json")])?;
+        assert_eq!(limits.storage, FREE_STORAGE_LIMIT);
+        assert_eq!(format, Format::Json);
+
+        let args = vec![String::from("--storage-limit"), String::from("10K")];
+        let (limits, format) = u.parse_opts(args)?;
+        assert_eq!(limits.storage, 10240);
+        assert_eq!(format, Format::Text);
+
+        assert_eq!(u.parse_opts(vec![String::from("--bogus")]).is_err(), true);
+
+        let mut output = Vec::new();
+        u.write_text(&list(), &limits, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("3.3 KiB of 10.0 KiB (33.2%)"), true);
+        assert_eq!(s.contains("images/"), true);
+        assert_eq!(s.contains("(root)"), true);
+
+        let mut output = Vec::new();
+        u.write_porcelain(&list(), &limits, &mut output)?;
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.starts_with("3400\t10240\t6840\n3000\t2\timages\n"), true);
+
+        match prev_limit {
+            Ok(p) => env::set_var(ENV_STORAGE_LIMIT, p),
+            Err(_) => env::remove_var(ENV_STORAGE_LIMIT),
+        }
+        match prev_supporter {
+            Ok(p) => env::set_var(ENV_SUPPORTER, p),
+            Err(_) => env::remove_var(ENV_SUPPORTER),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,606 @@
+use super::{
+    command::Executable,
+    diff::{Diff, Item},
+    filetype,
+    journal::{JournalEntry, JournalFile},
+    objects, trash,
+    upload::attempt_result,
+};
+use crate::{
+    api::{
+        credentials::{Credentials, ENV_VAR_MSG},
+        delete::{DeleteResponse, NcDelete},
+        list::File,
+        upload::{NcUpload, UploadResponse},
+    },
+    error::NeocitiesErr,
+};
+use chrono::Local;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+/// The string literal a user must type to run functionality in this module
+pub const KEY: &str = "watch";
+
+/// How long the directory must stay quiet after a change before it is deployed, so that a burst
+/// of changes, such as a build or a save, is sent together
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Deploys a local directory to a Neocities user's website whenever something in it changes
+pub struct Watch {
+    usage: String,
+    short: String,
+    long: String,
+}
+
+/// The options given to `watch`
+#[derive(Debug, PartialEq)]
+struct WatchOpts {
+    path: PathBuf,
+    debounce: Duration,
+    /// Apply the file type rules of the supporter plan
+    supporter: bool,
+}
+
+/// What the watcher passes on to the deploy loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Signal {
+    /// Something in the directory changed
+    Changed,
+    /// Ctrl-C was pressed
+    Stop,
+}
+
+/// The remote changes that bring the website in line with the directory
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    /// Paths of new or changed files
+    uploads: Vec<String>,
+    /// Paths that are gone, leaving out the contents of a directory that is gone as a whole
+    deletes: Vec<String>,
+}
+
+impl Watch {
+    /// A constructor that returns an instance of `Watch`.
+    pub fn new() -> Watch {
+        Watch {
+            usage: format!("\x1b[1;32m{KEY}\x1b[0m [<dir>] [--debounce <ms>] [--supporter]"),
+            short: String::from(DESC_SHORT),
+            long: String::from(DESC),
+        }
+    }
+
+    fn write(&self, msg: &str, mut writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        writer.write_all(msg.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes a line of the log, stamped with the local time.
+    fn log(&self, msg: &str, writer: impl std::io::Write) -> Result<(), NeocitiesErr> {
+        let line = format!(
+            "\x1b[90m[{}]\x1b[0m {msg}\n",
+            Local::now().format("%H:%M:%S")
+        );
+        self.write(line.as_str(), writer)
+    }
+
+    fn parse_opts(&self, args: Vec<String>) -> Result<WatchOpts, NeocitiesErr> {
+        let mut opts = WatchOpts {
+            path: PathBuf::from("."),
+            debounce: DEFAULT_DEBOUNCE,
+            supporter: filetype::is_supporter(),
+        };
+        let mut path: Option<PathBuf> = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--supporter" => opts.supporter = true,
+                "--debounce" => {
+                    opts.debounce = match args.get(i + 1).map(|n| n.parse::<u64>()) {
+                        Some(Ok(ms)) => Duration::from_millis(ms),
+                        _ => return Err(NeocitiesErr::InvalidArgument),
+                    };
+                    i += 1;
+                }
+                a if !a.starts_with('-') && path.is_none() => path = Some(PathBuf::from(a)),
+                _ => return Err(NeocitiesErr::InvalidArgument),
+            }
+            i += 1;
+        }
+
+        if let Some(p) = path {
+            opts.path = p;
+        }
+        if !opts.path.is_dir() {
+            return Err(NeocitiesErr::InvalidPath);
+        }
+
+        Ok(opts)
+    }
+
+    /// Reads the files in the directory, keyed by the paths they have on the website, as `diff`
+    /// maps them.
+    fn scan(&self, path: &Path) -> Result<HashMap<String, File>, NeocitiesErr> {
+        let mut items: HashMap<String, Item> = HashMap::new();
+        Diff::new().local_items(&mut items, path.to_path_buf())?;
+
+        Ok(items
+            .into_iter()
+            .map(|(k, item)| (k, item.file().clone()))
+            .collect())
+    }
+
+    /// Compares two scans of the directory. A file is uploaded if it is new or its contents
+    /// changed; a path is deleted if it is gone, unless its directory is gone as well, since
+    /// deleting the directory deletes it too.
+    fn changes(
+        &self,
+        root: &str,
+        before: &HashMap<String, File>,
+        after: &HashMap<String, File>,
+    ) -> Changes {
+        let mut uploads: Vec<String> = after
+            .iter()
+            .filter(|(_, f)| !f.is_directory)
+            .filter(|(k, f)| before.get(*k).map(|b| &b.sha1_hash) != Some(&f.sha1_hash))
+            .map(|(k, _)| k.clone())
+            .collect();
+        uploads.sort();
+
+        let gone: BTreeSet<&String> = before
+            .keys()
+            .filter(|k| !after.contains_key(*k) && k.as_str() != root)
+            .collect();
+        let deletes = gone
+            .iter()
+            .filter(|k| {
+                let mut parent = k.as_str();
+                while let Some((p, _)) = parent.rsplit_once('/') {
+                    if gone.contains(&p.to_string()) {
+                        return false;
+                    }
+                    parent = p;
+                }
+                true
+            })
+            .map(|k| k.to_string())
+            .collect();
+
+        Changes { uploads, deletes }
+    }
+
+    /// Waits for the next change and for the directory to go quiet after it. Returns `Stop` if
+    /// Ctrl-C is pressed in the meantime.
+    fn next_change(&self, rx: &Receiver<Signal>, debounce: Duration) -> Signal {
+        match rx.recv() {
+            Ok(Signal::Changed) => {}
+            _ => return Signal::Stop,
+        }
+
+        loop {
+            match rx.recv_timeout(debounce) {
+                Ok(Signal::Changed) => continue,
+                Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => return Signal::Stop,
+                Err(RecvTimeoutError::Timeout) => return Signal::Changed,
+            }
+        }
+    }
+
+    /// Sends the changes to the website and logs a line for each path. Files Neocities would
+    /// reject are skipped. Returns the paths that could not be uploaded or deleted, so that they
+    /// are tried again with the next change.
+    fn deploy(
+        &self,
+        changes: &Changes,
+        before: &HashMap<String, File>,
+        supporter: bool,
+        upload: impl Fn(Vec<String>) -> Result<UploadResponse, NeocitiesErr>,
+        delete: impl Fn(Vec<JournalFile>) -> Result<DeleteResponse, NeocitiesErr>,
+        mut writer: impl std::io::Write,
+    ) -> Result<Vec<String>, NeocitiesErr> {
+        let mut failed: Vec<String> = Vec::new();
+
+        let mut uploads: Vec<String> = Vec::new();
+        for path in changes.uploads.iter() {
+            match filetype::check(path, supporter) {
+                Ok(None) => uploads.push(path.clone()),
+                Ok(Some(reason)) => {
+                    let msg = format!("\x1b[93mSkipped\x1b[0m {path}: {reason}");
+                    self.log(msg.as_str(), &mut writer)?;
+                }
+                // the file may have gone again since the scan
+                Err(e) => {
+                    let msg = format!("\x1b[91mFailed\x1b[0m to read {path}: {e}");
+                    self.log(msg.as_str(), &mut writer)?;
+                    failed.push(path.clone());
+                }
+            }
+        }
+
+        if !uploads.is_empty() {
+            match attempt_result(upload(uploads.clone())) {
+                Ok(_) => {
+                    for path in uploads.iter() {
+                        let msg = format!("\x1b[92mUploaded\x1b[0m {path}");
+                        self.log(msg.as_str(), &mut writer)?;
+                    }
+                }
+                Err(e) => {
+                    let msg = format!(
+                        "\x1b[91mFailed\x1b[0m to upload {}: {e}",
+                        uploads.join(", ")
+                    );
+                    self.log(msg.as_str(), &mut writer)?;
+                    failed.extend(uploads);
+                }
+            }
+        }
+
+        if !changes.deletes.is_empty() {
+            let files: Vec<JournalFile> = changes
+                .deletes
+                .iter()
+                .map(|p| match before.get(p) {
+                    Some(f) => JournalFile::from(f),
+                    None => JournalFile {
+                        path: p.clone(),
+                        size: None,
+                        sha1_hash: None,
+                    },
+                })
+                .collect();
+
+            let result = match delete(files) {
+                Ok(res) if res.result == "success" => Ok(()),
+                Ok(res) => Err(match res.error_type {
+                    Some(t) => format!("{t}: {}", res.message),
+                    None => res.message,
+                }),
+                Err(e) => Err(e.to_string()),
+            };
+
+            match result {
+                Ok(_) => {
+                    for path in changes.deletes.iter() {
+                        let msg = format!("\x1b[91mDeleted\x1b[0m {path}");
+                        self.log(msg.as_str(), &mut writer)?;
+                    }
+                }
+                Err(e) => {
+                    let msg = format!(
+                        "\x1b[91mFailed\x1b[0m to delete {}: {e}",
+                        changes.deletes.join(", ")
+                    );
+                    self.log(msg.as_str(), &mut writer)?;
+                    failed.extend(changes.deletes.iter().cloned());
+                }
+            }
+        }
+
+        Ok(failed)
+    }
+}
+
+impl Default for Watch {
+    fn default() -> Watch {
+        Watch::new()
+    }
+}
+
+impl Executable for Watch {
+    fn run(&self, args: Vec<String>) -> Result<(), NeocitiesErr> {
+        let mut stdout = std::io::stdout();
+
+        let opts = self.parse_opts(args)?;
+
+        if !Credentials::have_env_vars() {
+            self.write(ENV_VAR_MSG, &mut stdout)?;
+            return Ok(());
+        }
+
+        let (tx, rx) = channel::<Signal>();
+
+        let stop = tx.clone();
+        ctrlc::set_handler(move || {
+            let _ = stop.send(Signal::Stop);
+        })?;
+
+        // reading the files to hash them is itself an event, which is ignored
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if let Ok(event) = res {
+                    if !matches!(event.kind, EventKind::Access(_)) {
+                        let _ = tx.send(Signal::Changed);
+                    }
+                }
+            })?;
+        watcher.watch(&opts.path, RecursiveMode::Recursive)?;
+
+        let root = Diff::new().format_path(&opts.path)?;
+        let mut snapshot = self.scan(&opts.path)?;
+
+        let msg = format!(
+            "Watching {} for changes; press Ctrl-C to stop",
+            opts.path.display()
+        );
+        self.log(msg.as_str(), &mut stdout)?;
+
+        // the site is looked up once, rather than for every change
+        let site = trash::sitename().ok();
+        let upload = |paths: Vec<String>| -> Result<UploadResponse, NeocitiesErr> {
+            let mut files: Vec<JournalFile> = Vec::new();
+            for path in paths.iter() {
+                files.push(objects::snapshot(path)?);
+            }
+
+            let data = NcUpload::fetch(paths)?;
+            JournalEntry::new("upload", KEY, site.clone(), files)
+                .respond(&data.result, data.error_type.clone(), &data.message)
+                .append()?;
+            Ok(data)
+        };
+        let delete = |files: Vec<JournalFile>| -> Result<DeleteResponse, NeocitiesErr> {
+            let paths: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+
+            let data = NcDelete::fetch(paths)?;
+            JournalEntry::new("delete", KEY, site.clone(), files)
+                .respond(&data.result, data.error_type.clone(), &data.message)
+                .append()?;
+            Ok(data)
+        };
+
+        while self.next_change(&rx, opts.debounce) == Signal::Changed {
+            let mut current = self.scan(&opts.path)?;
+            let changes = self.changes(&root, &snapshot, &current);
+            if changes == Changes::default() {
+                continue;
+            }
+
+            let failed = self.deploy(
+                &changes,
+                &snapshot,
+                opts.supporter,
+                upload,
+                delete,
+                &mut stdout,
+            )?;
+
+            // what failed is left as it was, so that it counts as a change next time
+            for path in failed.iter() {
+                match snapshot.get(path) {
+                    Some(f) => current.insert(path.clone(), f.clone()),
+                    None => current.remove(path),
+                };
+            }
+            snapshot = current;
+        }
+
+        self.log("Stopped watching", &mut stdout)?;
+
+        Ok(())
+    }
+
+    fn get_usage(&self) -> &str {
+        self.usage.as_str()
+    }
+
+    fn get_short_desc(&self) -> &str {
+        self.short.as_str()
+    }
+
+    fn get_long_desc(&self) -> &str {
+        self.long.as_str()
+    }
+}
+
+const DESC: &str = "Watch a local directory and deploy it to your Neocities website as you work. \
+Whenever files change, `watch` waits for the directory to go quiet (500 ms, or set \
+`--debounce <ms>`), then uploads the new and changed files and deletes the ones you removed, \
+using the same paths as `diff`. Each action is logged, and recorded in the journal like any \
+other upload or delete. Files Neocities would reject, and the paths in .neocitiesignore, are \
+skipped. Without a directory, it watches the current directory. Press Ctrl-C to stop.";
+
+const DESC_SHORT: &str = "Deploy local changes as they happen";
+
+#[cfg(test)]
+mod tests {
+    use super::{Changes, Signal, Watch, DEFAULT_DEBOUNCE, DESC, DESC_SHORT, KEY};
+    use crate::{
+        api::{delete::DeleteResponse, list::File, upload::UploadResponse},
+        client::{command::Executable, journal::JournalFile},
+        error::NeocitiesErr,
+    };
+    use std::{collections::HashMap, fs, path::PathBuf, sync::mpsc::channel, time::Duration};
+
+    #[test]
+    fn usage_desc_methods() {
+        let w = Watch::new();
+        assert_eq!(w.get_usage().contains(KEY), true);
+        assert_eq!(w.get_short_desc(), DESC_SHORT);
+        assert_eq!(w.get_long_desc(), DESC);
+    }
+
+    #[test]
+    fn parse_opts_method() -> Result<(), NeocitiesErr> {
+        let w = Watch::new();
+
+        let opts = w.parse_opts(vec![
+            String::from("tests/fixtures"),
+            String::from("--debounce"),
+            String::from("200"),
+            String::from("--supporter"),
+        ])?;
+        assert_eq!(opts.path.to_str(), Some("tests/fixtures"));
+        assert_eq!(opts.debounce, Duration::from_millis(200));
+        assert_eq!(opts.supporter, true);
+
+        // without a directory, the current one is watched, since files are uploaded under
+        // their paths from it
+        let opts = w.parse_opts(vec![])?;
+        assert_eq!(opts.debounce, DEFAULT_DEBOUNCE);
+        assert_eq!(opts.path, PathBuf::from("."));
+        assert_eq!(
+            w.parse_opts(vec![String::from("tests/fixtures/foo.html")])
+                .is_err(),
+            true
+        );
+        assert_eq!(
+            w.parse_opts(vec![String::from("--debounce")]).is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn scan_and_changes_methods() -> Result<(), NeocitiesErr> {
+        let w = Watch::new();
+        let temp_dir = tempfile::tempdir()?;
+        let site = temp_dir.path().join("site");
+        fs::create_dir_all(site.join("blog/2024"))?;
+        fs::write(site.join("index.html"), "<p>home</p>")?;
+        fs::write(site.join("about.html"), "<p>about</p>")?;
+        fs::write(site.join("blog/2024/post.html"), "<p>post</p>")?;
+        fs::write(site.join("blog/index.html"), "<p>blog</p>")?;
+
+        let root = crate::client::diff::Diff::new().format_path(&site)?;
+        let before = w.scan(&site)?;
+        assert_eq!(w.changes(&root, &before, &before), Changes::default());
+
+        fs::write(site.join("index.html"), "<p>home, again</p>")?;
+        fs::write(site.join("style.css"), "p {}")?;
+        fs::remove_file(site.join("about.html"))?;
+        fs::remove_dir_all(site.join("blog"))?;
+
+        let after = w.scan(&site)?;
+        let changes = w.changes(&root, &before, &after);
+        assert_eq!(
+            changes.uploads,
+            vec![format!("{root}/index.html"), format!("{root}/style.css")]
+        );
+        assert_eq!(
+            changes.deletes,
+            vec![format!("{root}/about.html"), format!("{root}/blog")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn next_change_method() {
+        let w = Watch::new();
+        let debounce = Duration::from_millis(10);
+
+        let (tx, rx) = channel();
+        tx.send(Signal::Changed).unwrap();
+        tx.send(Signal::Changed).unwrap();
+        assert_eq!(w.next_change(&rx, debounce), Signal::Changed);
+
+        tx.send(Signal::Changed).unwrap();
+        tx.send(Signal::Stop).unwrap();
+        assert_eq!(w.next_change(&rx, debounce), Signal::Stop);
+
+        drop(tx);
+        assert_eq!(w.next_change(&rx, debounce), Signal::Stop);
+    }
+
+    fn file(path: &str) -> File {
+        File {
+            path: path.to_string(),
+            is_directory: false,
+            size: Some(4),
+            updated_at: String::from("Sat, 13 Feb 2016 03:04:00 -0000"),
+            sha1_hash: Some(String::from("abc123")),
+        }
+    }
+
+    #[test]
+    fn deploy_method() -> Result<(), NeocitiesErr> {
+        let w = Watch::new();
+        let changes = Changes {
+            uploads: vec![
+                String::from("tests/fixtures/foo.html"),
+                String::from("tests/fixtures/gone.html"),
+            ],
+            deletes: vec![String::from("old.html")],
+        };
+        let before = HashMap::from([(String::from("old.html"), file("old.html"))]);
+
+        let upload = |paths: Vec<String>| {
+            assert_eq!(paths, vec!["tests/fixtures/foo.html"]);
+            Ok(UploadResponse {
+                result: String::from("success"),
+                error_type: None,
+                message: String::from("your file(s) have been successfully uploaded"),
+            })
+        };
+        let delete = |files: Vec<JournalFile>| -> Result<DeleteResponse, NeocitiesErr> {
+            assert_eq!(files[0].sha1_hash.as_deref(), Some("abc123"));
+            Err(NeocitiesErr::HttpRequestError("offline".into()))
+        };
+
+        let mut output = Vec::new();
+        let failed = w.deploy(&changes, &before, false, upload, delete, &mut output)?;
+        assert_eq!(failed, vec!["tests/fixtures/gone.html", "old.html"]);
+
+        let s = String::from_utf8(output)?;
+        assert_eq!(
+            s.contains("Uploaded\x1b[0m tests/fixtures/foo.html\n"),
+            true
+        );
+        assert_eq!(
+            s.contains("Failed\x1b[0m to read tests/fixtures/gone.html"),
+            true
+        );
+        assert_eq!(
+            s.contains("Failed\x1b[0m to delete old.html: offline\n"),
+            true
+        );
+
+        // a request that Neocities answers with an error is a failure too
+        let upload = |_| {
+            Ok(UploadResponse {
+                result: String::from("error"),
+                error_type: Some(String::from("too_large")),
+                message: String::from("foo.html is too large"),
+            })
+        };
+        let delete = |_| {
+            Ok(DeleteResponse {
+                result: String::from("error"),
+                error_type: Some(String::from("missing_files")),
+                message: String::from("old.html was not found"),
+            })
+        };
+
+        let mut output = Vec::new();
+        let failed = w.deploy(&changes, &before, false, upload, delete, &mut output)?;
+        assert_eq!(
+            failed,
+            vec![
+                "tests/fixtures/gone.html",
+                "tests/fixtures/foo.html",
+                "old.html"
+            ]
+        );
+
+        let s = String::from_utf8(output)?;
+        assert_eq!(s.contains("Uploaded"), false);
+        assert_eq!(
+            s.contains("to upload tests/fixtures/foo.html: too_large: foo.html is too large\n"),
+            true
+        );
+        assert_eq!(
+            s.contains("to delete old.html: missing_files: old.html was not found\n"),
+            true
+        );
+
+        Ok(())
+    }
+}
@@ -27,6 +27,92 @@ pub enum NeocitiesErr {
     #[error("file at specified path does not exist")]
     MissingFile,
 
+    /// A directory was given where a file was expected
+    #[error("target is a directory: use -r to delete it recursively")]
+    IsDirectory,
+
+    /// A protected path was targeted without explicit permission
+    #[error("refusing to delete a protected path: use --allow-protected to delete it")]
+    ProtectedPath,
+
+    /// A confirmation was needed but there is no terminal to ask it on
+    #[error("confirmation required but stdin is not a terminal: use --yes to skip it")]
+    NotInteractive,
+
+    /// A copy downloaded for the trash did not match the file on the website
+    #[error("a downloaded copy does not match the file on your site; nothing was deleted")]
+    TrashMismatch,
+
+    /// No trash entry exists with the given id
+    #[error("no trash entry with that id: see `trash list`")]
+    UnknownTrashId,
+
+    /// A trash entry was saved from a website other than the one of the credentials
+    #[error("the trash entry was saved from another website; nothing was restored")]
+    TrashSiteMismatch,
+
+    /// No journal entry exists with the given id
+    #[error("no deployment with that id: see `log`")]
+    UnknownDeployId,
+
+    /// The contents needed for a rollback are missing from the object store
+    #[error("a file needed for the rollback is not in the local object store")]
+    MissingObject,
+
+    /// `upload --resume` was run, but there is no unfinished upload
+    #[error("there is no unfinished upload to resume")]
+    NoUploadToResume,
+
+    /// Some files of an upload failed
+    #[error("some files failed to upload: run `upload --resume` to retry them")]
+    UploadFailed,
+
+    /// Some uploaded files do not match the remote listing
+    #[error(
+        "some uploaded files do not match your site: run `upload --resume` to send them again"
+    )]
+    VerifyFailed,
+
+    /// Some files are of a type Neocities does not accept, or do not match their extension
+    #[error("some files cannot be uploaded to Neocities: nothing was sent")]
+    DisallowedFileType,
+
+    /// An upload would take a website past its storage limit
+    #[error("this upload would exceed your storage limit: see `usage`, or set --storage-limit")]
+    QuotaExceeded,
+
+    /// The editor opened by `edit` did not exit cleanly
+    #[error("the editor exited with an error: nothing was uploaded")]
+    EditorFailed,
+
+    /// A file changed on the website while it was being edited
+    #[error("the file changed on your site while you were editing it: nothing was uploaded")]
+    EditConflict,
+
+    /// A file already exists at a path that was expected to be free
+    #[error("a file already exists at the destination path")]
+    FileExists,
+
+    /// A deployment plan no longer matches the current local or remote state
+    #[error("the plan no longer matches the current state; create a new plan")]
+    StalePlan,
+
+    /// The sitename given to `init` is not the website of the credentials in the environment
+    #[error("--sitename does not match the website of your credentials")]
+    SitenameMismatch,
+
+    /// Some of the files of a move could not be renamed
+    #[error("some files could not be moved: see the status of each above")]
+    MoveFailed,
+
+    /// Neocities answered a request with an error result
+    #[error("Neocities refused the request: see the status above")]
+    RequestFailed,
+
+    /// The state of a local path differs from its corresponding remote path
+    #[error("local and remote are out of sync")]
+    OutOfSync,
+
     /// A problem occurred while deserializing json data
     #[error(transparent)]
     SerdeDeserializationError(#[from] serde_json::Error),
@@ -58,4 +144,12 @@ pub enum NeocitiesErr {
     /// Could not determine time
     #[error(transparent)]
     SystemTimeError(#[from] std::time::SystemTimeError),
+
+    /// An error occurred while watching files for changes
+    #[error(transparent)]
+    NotifyError(#[from] notify::Error),
+
+    /// Could not set up the handler for Ctrl-C
+    #[error(transparent)]
+    CtrlcError(#[from] ctrlc::Error),
 }
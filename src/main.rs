@@ -1,6 +1,15 @@
-use neocities_cli::client::{config::Config, runner::run};
+use neocities_cli::{
+    client::{config::Config, runner::run},
+    error::NeocitiesErr,
+};
 use std::{env, process};
 
+/// The exit status of `diff --exit-code` when local and remote are out of sync
+const EXIT_OUT_OF_SYNC: i32 = 1;
+
+/// The exit status of a command that failed
+const EXIT_ERROR: i32 = 2;
+
 // The main entry point of the program.
 fn main() {
     // Collect command-line arguments into a vector of strings.
@@ -10,11 +19,19 @@ fn main() {
     let config = Config::build(&input);
 
     // Attempt to run the program with the provided configuration.
-    if let Err(e) = run(config) {
-        // Print an error message in bold text, followed by the error details.
-        eprintln!("\x1b[1mError: \x1b[0m{e}");
+    match run(config) {
+        Ok(_) => {}
+
+        // A difference is not a failure, so it gets its own status and no error message, like
+        // `git diff --exit-code`.
+        Err(NeocitiesErr::OutOfSync) => process::exit(EXIT_OUT_OF_SYNC),
+
+        Err(e) => {
+            // Print an error message in bold text, followed by the error details.
+            eprintln!("\x1b[1mError: \x1b[0m{e}");
 
-        // Exit the program with a non-zero status code to indicate an error.
-        process::exit(1);
+            // Exit the program with a status code that tells an error apart from a difference.
+            process::exit(EXIT_ERROR);
+        }
     }
 }